@@ -0,0 +1,354 @@
+//! Clockwise ordering of the dual boundary and the sides of the chip.
+//!
+//! [`search_cutlines`](crate::cutline::search_cutlines) pairs every boundary
+//! router with every other one. [`clockwise_boundary`] orders the routers
+//! clockwise around the chip, starting from its top-left corner, and labels
+//! each of them with the side of the chip it lies on. [`Endpoints`] in the
+//! algorithm config then restricts the search to the cutlines running
+//! between two sides or two arcs of the boundary, e.g. `top:bottom` for the
+//! bisections crossing the chip from top to bottom.
+//!
+//! The rows of the grid grow downwards, so the top side holds the smallest
+//! `y`. The dual nodes of a loaded layout have no coordinates, they are
+//! placed at the mean midpoint of the couplers their dual edges cross.
+use crate::graph::{Lattice, Point, SearchGraph};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Side::Top => "top",
+            Side::Right => "right",
+            Side::Bottom => "bottom",
+            Side::Left => "left",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A boundary router and the side of the chip it lies on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoundaryRouter {
+    pub node: Point,
+    pub side: Side,
+}
+
+/// The boundary routers of the graph in clockwise order, starting from the
+/// top-left corner of the chip.
+///
+/// A router is on the top or bottom side when it is further from the center
+/// vertically than horizontally, relative to the extent of the qubits, so
+/// that the corners belong to the top and bottom sides.
+pub fn clockwise_boundary(graph: &SearchGraph) -> Vec<BoundaryRouter> {
+    let (min_x, max_x) = graph
+        .primal
+        .nodes()
+        .map(|q| q.0)
+        .minmax()
+        .into_option()
+        .unwrap();
+    let (min_y, max_y) = graph
+        .primal
+        .nodes()
+        .map(|q| q.1)
+        .minmax()
+        .into_option()
+        .unwrap();
+    let center = ((min_x + max_x) as f64 / 2.0, (min_y + max_y) as f64 / 2.0);
+    let half_width = ((max_x - min_x) as f64 / 2.0).max(1.0);
+    let half_height = ((max_y - min_y) as f64 / 2.0).max(1.0);
+    // the top-left corner starts the boundary
+    let start = (-half_height).atan2(-half_width) - 1e-9;
+    graph
+        .dual_boundaries
+        .iter()
+        .map(|&node| {
+            let (x, y) = dual_position(graph, node);
+            let (dx, dy) = ((x - center.0) / half_width, (y - center.1) / half_height);
+            let side = match (dy.abs() >= dx.abs(), dy < 0.0, dx < 0.0) {
+                (true, true, _) => Side::Top,
+                (true, false, _) => Side::Bottom,
+                (false, _, true) => Side::Left,
+                (false, _, false) => Side::Right,
+            };
+            // with y downwards the angle grows clockwise
+            let angle = ((y - center.1).atan2(x - center.0) - start).rem_euclid(2.0 * PI);
+            (angle, BoundaryRouter { node, side })
+        })
+        .sorted_by(|(a1, r1), (a2, r2)| a1.partial_cmp(a2).unwrap().then(r1.node.cmp(&r2.node)))
+        .map(|(_, router)| router)
+        .collect()
+}
+
+/// The position of a dual node in the coordinates of the qubits.
+fn dual_position(graph: &SearchGraph, node: Point) -> (f64, f64) {
+    if graph.config.layout.is_none() && graph.config.lattice == Lattice::Rotated {
+        return (node.0 as f64, node.1 as f64);
+    }
+    let midpoints = graph
+        .dual
+        .neighbors(node)
+        .filter_map(|n| graph.crossed_edge(node, n))
+        .map(|(q1, q2)| ((q1.0 + q2.0) as f64 / 2.0, (q1.1 + q2.1) as f64 / 2.0))
+        .collect_vec();
+    let n = midpoints.len().max(1) as f64;
+    let (x, y) = midpoints
+        .iter()
+        .fold((0.0, 0.0), |(x, y), p| (x + p.0, y + p.1));
+    (x / n, y / n)
+}
+
+/// A part of the boundary, a side of the chip or the routers between two
+/// positions of the clockwise boundary.
+///
+/// The text form is the side name, or `i..j` for the routers from position
+/// `i` to position `j` included, wrapping around the top-left corner when
+/// `j` is smaller than `i`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoundaryArc {
+    Side(Side),
+    Range(usize, usize),
+}
+
+impl BoundaryArc {
+    /// Whether the router at this position of the clockwise boundary is part
+    /// of the arc.
+    pub fn contains(&self, position: usize, router: &BoundaryRouter) -> bool {
+        match *self {
+            BoundaryArc::Side(side) => router.side == side,
+            BoundaryArc::Range(i, j) if i <= j => (i..=j).contains(&position),
+            BoundaryArc::Range(i, j) => position >= i || position <= j,
+        }
+    }
+}
+
+impl fmt::Display for BoundaryArc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoundaryArc::Side(side) => write!(f, "{}", side),
+            BoundaryArc::Range(i, j) => write!(f, "{}..{}", i, j),
+        }
+    }
+}
+
+impl FromStr for BoundaryArc {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let side = match s {
+            "top" => Some(Side::Top),
+            "right" => Some(Side::Right),
+            "bottom" => Some(Side::Bottom),
+            "left" => Some(Side::Left),
+            _ => None,
+        };
+        if let Some(side) = side {
+            return Ok(BoundaryArc::Side(side));
+        }
+        let invalid = || {
+            format!(
+                "invalid boundary arc '{}', expected 'top', 'right', 'bottom', 'left' or 'i..j'",
+                s
+            )
+        };
+        let (i, j) = s.split_once("..").ok_or_else(invalid)?;
+        let i = i.trim().parse().map_err(|_| invalid())?;
+        let j = j.trim().parse().map_err(|_| invalid())?;
+        Ok(BoundaryArc::Range(i, j))
+    }
+}
+
+/// The two arcs of the boundary the cutlines run between, in either
+/// direction. The text form is `from:to`, e.g. `top:bottom` or `0..5:20..30`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Endpoints {
+    pub from: BoundaryArc,
+    pub to: BoundaryArc,
+}
+
+impl Endpoints {
+    /// Whether a cutline may run between the routers at these positions of
+    /// the clockwise boundary.
+    pub fn connects(&self, r1: (usize, &BoundaryRouter), r2: (usize, &BoundaryRouter)) -> bool {
+        let (from, to) = (&self.from, &self.to);
+        (from.contains(r1.0, r1.1) && to.contains(r2.0, r2.1))
+            || (from.contains(r2.0, r2.1) && to.contains(r1.0, r1.1))
+    }
+
+    /// The positions in the arcs which are not on the clockwise boundary of
+    /// the graph.
+    pub fn out_of_range(&self, boundary_len: usize) -> Vec<usize> {
+        [self.from, self.to]
+            .into_iter()
+            .flat_map(|arc| match arc {
+                BoundaryArc::Side(_) => vec![],
+                BoundaryArc::Range(i, j) => vec![i, j],
+            })
+            .filter(|&i| i >= boundary_len)
+            .collect()
+    }
+}
+
+impl fmt::Display for Endpoints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.from, self.to)
+    }
+}
+
+impl FromStr for Endpoints {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (from, to) = s.split_once(':').ok_or_else(|| {
+            format!(
+                "invalid endpoints '{}', expected 'from:to' with two boundary arcs",
+                s
+            )
+        })?;
+        Ok(Endpoints {
+            from: from.parse()?,
+            to: to.parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AlgorithmConfigBuilder, TopologyConfigBuilder};
+    use crate::cutline::{search_cutlines, CutlineError};
+    use crate::session::SearchSession;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_clockwise_boundary() {
+        let graph = SearchGraph::default();
+        let boundary = clockwise_boundary(&graph);
+        assert_eq!(boundary.len(), graph.dual_boundaries.len());
+        // the sides follow each other once around the chip
+        let sides = boundary.iter().map(|r| r.side).dedup().collect_vec();
+        assert_eq!(sides, [Side::Top, Side::Right, Side::Bottom, Side::Left]);
+        assert_eq!(boundary[0].node, (0, 0));
+        assert!(boundary
+            .iter()
+            .filter(|r| r.side == Side::Right)
+            .all(|r| r.node.0 == 11));
+        assert!(boundary
+            .iter()
+            .filter(|r| r.side == Side::Bottom)
+            .all(|r| r.node.1 == 10));
+
+        let config = TopologyConfigBuilder::default()
+            .width(4)
+            .height(3)
+            .lattice(Lattice::Square)
+            .build()
+            .unwrap();
+        let graph = SearchGraph::from_config(config).unwrap();
+        let boundary = clockwise_boundary(&graph);
+        let sides = boundary.iter().map(|r| r.side).collect_vec();
+        assert_eq!(
+            sides,
+            [
+                [Side::Top; 3].as_slice(),
+                &[Side::Right; 2],
+                &[Side::Bottom; 3],
+                &[Side::Left; 2]
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn test_endpoints() {
+        let endpoints: Endpoints = "top:3..1".parse().unwrap();
+        assert_eq!(endpoints.from, BoundaryArc::Side(Side::Top));
+        assert_eq!(endpoints.to, BoundaryArc::Range(3, 1));
+        assert_eq!(endpoints.to_string(), "top:3..1");
+        assert_eq!(endpoints.out_of_range(3), vec![3]);
+        let router = BoundaryRouter {
+            node: (0, 0),
+            side: Side::Left,
+        };
+        assert!(endpoints.to.contains(0, &router));
+        assert!(!endpoints.to.contains(2, &router));
+        for invalid in ["top", "up:down", "top:1..", "top:a..2"] {
+            assert!(invalid.parse::<Endpoints>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_search_between_sides() {
+        let topology = TopologyConfigBuilder::default()
+            .width(6)
+            .height(6)
+            .build()
+            .unwrap();
+        let graph = SearchGraph::from_config(topology).unwrap();
+        let algorithm = |endpoints: Option<&str>| {
+            AlgorithmConfigBuilder::default()
+                .max_depth(6)
+                .endpoints(endpoints.map(|e| e.parse().unwrap()))
+                .build()
+                .unwrap()
+        };
+        let keys = |endpoints: Option<&str>| {
+            search_cutlines(&graph, &algorithm(endpoints))
+                .unwrap()
+                .into_iter()
+                .map(|c| {
+                    c.into_wrapped(&graph)
+                        .split
+                        .into_iter()
+                        .sorted()
+                        .collect_vec()
+                })
+                .collect::<HashSet<_>>()
+        };
+        let all = keys(None);
+        let top_bottom = keys(Some("top:bottom"));
+        assert!(!top_bottom.is_empty() && top_bottom.len() < all.len());
+
+        // every cutline runs between two of the sides
+        let sides = ["top", "right", "bottom", "left"];
+        let union = sides
+            .iter()
+            .tuple_combinations()
+            .map(|(s1, s2)| format!("{}:{}", s1, s2))
+            .chain(sides.iter().map(|s| format!("{}:{}", s, s)))
+            .flat_map(|e| keys(Some(&e)))
+            .collect::<HashSet<_>>();
+        assert_eq!(union, all);
+
+        let n = clockwise_boundary(&graph).len();
+        assert_eq!(keys(Some(&format!("0..{}:0..{}", n - 1, n - 1))), all);
+
+        // the positions beyond the boundary are rejected by the library
+        let beyond = algorithm(Some(&format!("top:2..{}", n + 1)));
+        let error = CutlineError::EndpointsOutOfRange {
+            endpoints: beyond.endpoints.unwrap(),
+            positions: vec![n + 1],
+            n_routers: n,
+        };
+        assert_eq!(search_cutlines(&graph, &beyond), Err(error.clone()));
+        assert_eq!(
+            SearchSession::new(graph.clone(), beyond).unwrap_err(),
+            error
+        );
+    }
+}
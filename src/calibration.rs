@@ -0,0 +1,343 @@
+use crate::config::TopologyConfig;
+use crate::input::{InputError, InputFile};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// The error rates measured by a calibration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    SingleQubit,
+    Readout,
+    TwoQubit,
+}
+
+impl Metric {
+    fn name(&self) -> &'static str {
+        match self {
+            Metric::SingleQubit => "single_qubit",
+            Metric::Readout => "readout",
+            Metric::TwoQubit => "two_qubit",
+        }
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A qubit or a coupler, by the row-major qubit indices used by
+/// `unused_qubits` and `unused_couplers`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Target {
+    Qubit(u32),
+    Coupler(u32, u32),
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::Qubit(i) => write!(f, "qubit {}", i),
+            Target::Coupler(i1, i2) => write!(f, "coupler ({}, {})", i1, i2),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationEntry {
+    pub metric: Metric,
+    pub target: Target,
+    pub error: f64,
+}
+
+/// The error rates of a calibration run of the chip.
+///
+/// The CSV form has a `metric,qubits,error` line for each measured error,
+/// where the metric is `single_qubit`, `readout` or `two_qubit` and the
+/// qubits are a qubit index, or the two qubit indices of a coupler joined by
+/// `-`. A header line starting with `metric`, empty lines and lines starting
+/// with `#` are skipped:
+///
+/// ```text
+/// metric,qubits,error
+/// single_qubit,0,0.0016
+/// readout,0,0.031
+/// two_qubit,0-6,0.0062
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Calibration {
+    pub entries: Vec<CalibrationEntry>,
+}
+
+/// The maximum error of each metric, a qubit or coupler above any of them
+/// is disabled. A metric without threshold disables nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Thresholds {
+    pub single_qubit: Option<f64>,
+    pub readout: Option<f64>,
+    pub two_qubit: Option<f64>,
+}
+
+impl Thresholds {
+    pub fn get(&self, metric: Metric) -> Option<f64> {
+        match metric {
+            Metric::SingleQubit => self.single_qubit,
+            Metric::Readout => self.readout,
+            Metric::TwoQubit => self.two_qubit,
+        }
+    }
+}
+
+/// A qubit or coupler disabled by a calibration entry above its threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Disabled {
+    pub target: Target,
+    pub metric: Metric,
+    pub error: f64,
+    pub threshold: f64,
+}
+
+impl fmt::Display for Disabled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} error {} above {}",
+            self.target, self.metric, self.error, self.threshold
+        )
+    }
+}
+
+/// What a calibration disabled and why, sorted by target.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct CalibrationSummary {
+    pub disabled: Vec<Disabled>,
+}
+
+impl CalibrationSummary {
+    pub fn disabled_qubits(&self) -> Vec<u32> {
+        self.disabled
+            .iter()
+            .filter_map(|d| match d.target {
+                Target::Qubit(i) => Some(i),
+                Target::Coupler(..) => None,
+            })
+            .dedup()
+            .collect()
+    }
+
+    pub fn disabled_couplers(&self) -> Vec<(u32, u32)> {
+        self.disabled
+            .iter()
+            .filter_map(|d| match d.target {
+                Target::Qubit(_) => None,
+                Target::Coupler(i1, i2) => Some((i1, i2)),
+            })
+            .dedup()
+            .collect()
+    }
+}
+
+impl fmt::Display for CalibrationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Disabled {} qubits and {} couplers",
+            self.disabled_qubits().len(),
+            self.disabled_couplers().len()
+        )?;
+        for disabled in &self.disabled {
+            write!(f, "\n  {}", disabled)?;
+        }
+        Ok(())
+    }
+}
+
+impl Calibration {
+    pub fn from_file(path: &Path) -> Result<Self, InputError> {
+        let calibration_error =
+            |message: String| InputError::new(InputFile::Calibration, path, message);
+        let content =
+            std::fs::read_to_string(path).map_err(|e| calibration_error(e.to_string()))?;
+        Self::from_csv(&content).map_err(calibration_error)
+    }
+
+    pub fn from_csv(content: &str) -> Result<Self, String> {
+        let mut entries = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with("metric") {
+                continue;
+            }
+            let invalid = |message: String| format!("line {}: {}", i + 1, message);
+            let fields = line.split(',').map(str::trim).collect_vec();
+            let [metric, qubits, error] = fields[..] else {
+                return Err(invalid(format!(
+                    "expected 'metric,qubits,error', found '{}'",
+                    line
+                )));
+            };
+            let metric = match metric {
+                "single_qubit" => Metric::SingleQubit,
+                "readout" => Metric::Readout,
+                "two_qubit" => Metric::TwoQubit,
+                _ => {
+                    return Err(invalid(format!(
+                        "unknown metric '{}', expected 'single_qubit', 'readout' or 'two_qubit'",
+                        metric
+                    )))
+                }
+            };
+            let index = |s: &str| {
+                s.trim()
+                    .parse::<u32>()
+                    .map_err(|_| invalid(format!("invalid qubit index '{}'", s)))
+            };
+            let target = match qubits.split_once('-') {
+                Some((i1, i2)) => {
+                    let (i1, i2) = (index(i1)?, index(i2)?);
+                    Target::Coupler(i1.min(i2), i1.max(i2))
+                }
+                None => Target::Qubit(index(qubits)?),
+            };
+            if matches!(target, Target::Coupler(..)) != (metric == Metric::TwoQubit) {
+                return Err(invalid(format!(
+                    "the {} error does not apply to {}",
+                    metric, target
+                )));
+            }
+            let error = error
+                .parse::<f64>()
+                .map_err(|_| invalid(format!("invalid error '{}'", error)))?;
+            entries.push(CalibrationEntry {
+                metric,
+                target,
+                error,
+            });
+        }
+        Ok(Self { entries })
+    }
+
+    /// Disable the qubits and couplers of the topology whose error is above
+    /// the threshold of its metric. The connectivity of the remaining qubits
+    /// is checked when building the [`SearchGraph`](crate::graph::SearchGraph)
+    /// of the returned topology.
+    pub fn apply(
+        &self,
+        topology: &TopologyConfig,
+        thresholds: &Thresholds,
+    ) -> (TopologyConfig, CalibrationSummary) {
+        let disabled = self
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                let threshold = thresholds.get(entry.metric)?;
+                (entry.error > threshold).then_some(Disabled {
+                    target: entry.target,
+                    metric: entry.metric,
+                    error: entry.error,
+                    threshold,
+                })
+            })
+            .sorted_by_key(|d| d.target)
+            .collect_vec();
+        let summary = CalibrationSummary { disabled };
+        let mut topology = topology.clone();
+        for i in summary.disabled_qubits() {
+            if !topology.unused_qubits.contains(&i) {
+                topology.unused_qubits.push(i);
+            }
+        }
+        for (i1, i2) in summary.disabled_couplers() {
+            if !topology.unused_couplers.contains(&(i1, i2))
+                && !topology.unused_couplers.contains(&(i2, i1))
+            {
+                topology.unused_couplers.push((i1, i2));
+            }
+        }
+        (topology, summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{GraphError, SearchGraph};
+
+    const CSV: &str = "metric,qubits,error
+# a 4x3 grid
+single_qubit,0,0.0012
+readout,0,0.052
+readout,3,0.021
+single_qubit,4,0.0051
+readout,4,0.048
+two_qubit,3-1,0.031
+two_qubit,3-4,0.0061
+";
+
+    #[test]
+    fn test_parse_calibration() {
+        let calibration = Calibration::from_csv(CSV).unwrap();
+        assert_eq!(calibration.entries.len(), 7);
+        assert_eq!(
+            calibration.entries[5],
+            CalibrationEntry {
+                metric: Metric::TwoQubit,
+                target: Target::Coupler(1, 3),
+                error: 0.031,
+            }
+        );
+        for invalid in [
+            "readout,0",
+            "leakage,0,0.01",
+            "readout,0-1,0.01",
+            "two_qubit,0,0.01",
+            "readout,a,0.01",
+            "readout,0,high",
+        ] {
+            assert!(Calibration::from_csv(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_apply_calibration() {
+        let calibration = Calibration::from_csv(CSV).unwrap();
+        let topology = TopologyConfig {
+            width: 4,
+            height: 3,
+            unused_qubits: vec![4],
+            ..Default::default()
+        };
+        let thresholds = Thresholds {
+            readout: Some(0.04),
+            two_qubit: Some(0.01),
+            ..Default::default()
+        };
+        let (topology, summary) = calibration.apply(&topology, &thresholds);
+        assert_eq!(summary.disabled_qubits(), vec![0, 4]);
+        assert_eq!(summary.disabled_couplers(), vec![(1, 3)]);
+        assert_eq!(topology.unused_qubits, vec![4, 0]);
+        assert_eq!(topology.unused_couplers, vec![(1, 3)]);
+        assert_eq!(
+            summary.to_string(),
+            "Disabled 2 qubits and 1 couplers
+  qubit 0: readout error 0.052 above 0.04
+  qubit 4: readout error 0.048 above 0.04
+  coupler (1, 3): two_qubit error 0.031 above 0.01"
+        );
+        // qubit 1 only keeps its dead coupler to qubit 3
+        assert!(matches!(
+            SearchGraph::from_config(topology),
+            Err(GraphError::Disconnected { .. })
+        ));
+
+        let (topology, summary) =
+            calibration.apply(&TopologyConfig::default(), &Default::default());
+        assert!(summary.disabled.is_empty());
+        assert_eq!(topology, TopologyConfig::default());
+    }
+}
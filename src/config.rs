@@ -1,9 +1,14 @@
+use crate::boundary::Endpoints;
+use crate::cost::CostModelKind;
+use crate::defect::Defect;
+use crate::graph::Lattice;
+use crate::modular::Tile;
 use crate::pattern::Order;
 use anyhow::Result;
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 use std::fs::File;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Builder, Clone)]
 pub struct TopologyConfig {
@@ -17,6 +22,35 @@ pub struct TopologyConfig {
     pub unused_couplers: Vec<(u32, u32)>,
     #[builder(default = "false")]
     pub qubit_at_origin: bool,
+    /// The lattice generated from the width and height, the origin is only
+    /// used by the rotated lattice
+    #[builder(default)]
+    #[serde(default)]
+    pub lattice: Lattice,
+    /// Dead qubits and couplers given by coordinates, in addition to the
+    /// unused qubits and couplers above
+    #[builder(default = "Vec::new()")]
+    #[serde(default)]
+    pub defects: Vec<Defect>,
+    /// Keep the largest connected component of the used qubits and mark
+    /// the other islands unused instead of rejecting the topology
+    #[builder(default = "false")]
+    #[serde(default)]
+    pub drop_islands: bool,
+    /// Load the coupling graph from a layout file instead of generating a
+    /// rotated grid, see [`Layout`](crate::layout::Layout)
+    #[builder(default = "None")]
+    #[serde(default)]
+    pub layout: Option<PathBuf>,
+    /// Only keep the qubits of these tiles of the rotated grid, see
+    /// [`modular`](crate::modular)
+    #[builder(default = "Vec::new()")]
+    #[serde(default)]
+    pub tiles: Vec<Tile>,
+    /// Long-range couplers between the qubits of two tiles
+    #[builder(default = "Vec::new()")]
+    #[serde(default)]
+    pub links: Vec<(u32, u32)>,
 }
 
 impl Default for TopologyConfig {
@@ -42,6 +76,18 @@ pub struct AlgorithmConfig {
     pub patterns: Option<Vec<String>>,
     #[builder(default = "usize::MAX")]
     pub max_patterns: usize,
+    #[builder(default)]
+    #[serde(default)]
+    pub cost_model: CostModelKind,
+    /// Wall-clock budget of the pattern search in seconds
+    #[builder(default = "None")]
+    #[serde(default)]
+    pub time_budget: Option<u64>,
+    /// Only search the cutlines between these two arcs of the boundary, see
+    /// [`Endpoints`]
+    #[builder(default = "None")]
+    #[serde(default)]
+    pub endpoints: Option<Endpoints>,
 }
 
 impl Default for AlgorithmConfig {
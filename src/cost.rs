@@ -1,27 +1,37 @@
-use std::fmt::{Debug, Write};
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
+use crate::cancel::CancelToken;
 use crate::config::AlgorithmConfig;
 use crate::cutline::{Cutline, CutlineWrapped};
 use crate::graph::SearchGraph;
-use crate::pattern::{BitPattern, Order, Pattern};
+use crate::pattern::{Order, Pattern, PatternDescription};
+use crate::progress::ProgressObserver;
+use clap::ValueEnum;
 use fixedbitset::FixedBitSet;
-use indicatif::ParallelProgressIterator;
-use indicatif::{ProgressBar, ProgressState, ProgressStyle};
 use itertools::Itertools;
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Cost {
-    gates: usize,
-    start_end: usize,
-    wedge: usize,
-    dcd: usize,
-    unbalance: usize,
+    /// Number of two qubit gates on the cutline
+    pub gates: usize,
+    /// Number of gates elided at the start and the end of the circuit
+    pub start_end: usize,
+    /// Number of wedge fusions
+    pub wedge: usize,
+    /// Number of gates saved by DCD fusions
+    pub dcd: usize,
+    /// Unbalance of the two parts split by the cutline
+    pub unbalance: usize,
 }
 
 impl Cost {
+    /// Effective number of cut gates after fusions and elisions.
     #[inline]
-    fn cut_length(&self) -> f64 {
+    pub fn cut_length(&self) -> f64 {
         (self.gates - self.dcd - self.wedge) as f64 - self.start_end as f64 / 2f64
     }
 
@@ -33,6 +43,97 @@ impl Cost {
     }
 }
 
+/// A cost estimate of the SFA simulation along a cutline.
+///
+/// The cutline with the lowest score is the optimal cutline of a pattern,
+/// and the patterns with the highest optimal score win the search.
+pub trait CostModel: Send + Sync {
+    fn score(&self, cost: &Cost) -> f64;
+}
+
+/// `4^(length + unbalance/4) + 4^(length - unbalance/4)`, see [`Cost::cost`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StandardCost;
+
+impl CostModel for StandardCost {
+    #[inline]
+    fn score(&self, cost: &Cost) -> f64 {
+        cost.cost()
+    }
+}
+
+/// Number of Feynman paths `4^length`, ignoring the unbalance.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PathCount;
+
+impl CostModel for PathCount {
+    #[inline]
+    fn score(&self, cost: &Cost) -> f64 {
+        4f64.powf(cost.cut_length())
+    }
+}
+
+/// Number of paths times the Schrödinger memory of the larger part.
+#[derive(Debug, Clone, Copy)]
+pub struct PathMemory {
+    pub n_qubits: usize,
+}
+
+impl CostModel for PathMemory {
+    #[inline]
+    fn score(&self, cost: &Cost) -> f64 {
+        let (_, larger) = part_sizes(self.n_qubits, cost.unbalance);
+        4f64.powf(cost.cut_length()) * 2f64.powf(larger)
+    }
+}
+
+/// Number of paths times the summed Schrödinger cost of both parts.
+#[derive(Debug, Clone, Copy)]
+pub struct PartSize {
+    pub n_qubits: usize,
+}
+
+impl CostModel for PartSize {
+    #[inline]
+    fn score(&self, cost: &Cost) -> f64 {
+        let (smaller, larger) = part_sizes(self.n_qubits, cost.unbalance);
+        4f64.powf(cost.cut_length()) * (2f64.powf(smaller) + 2f64.powf(larger))
+    }
+}
+
+#[inline]
+fn part_sizes(n_qubits: usize, unbalance: usize) -> (f64, f64) {
+    let smaller = n_qubits.saturating_sub(unbalance) as f64 / 2f64;
+    (smaller, smaller + unbalance as f64)
+}
+
+/// The built-in cost models which can be selected from the config.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum CostModelKind {
+    /// `4^(length + unbalance/4) + 4^(length - unbalance/4)`
+    #[default]
+    Standard,
+    /// `4^length`
+    PathCount,
+    /// `4^length * 2^larger_part`
+    PathMemory,
+    /// `4^length * (2^part0 + 2^part1)`
+    PartSize,
+}
+
+impl CostModelKind {
+    pub fn build(&self, graph: &SearchGraph) -> Box<dyn CostModel> {
+        let n_qubits = graph.primal.node_count() - graph.unused_qubits.len();
+        match self {
+            CostModelKind::Standard => Box::new(StandardCost),
+            CostModelKind::PathCount => Box::new(PathCount),
+            CostModelKind::PathMemory => Box::new(PathMemory { n_qubits }),
+            CostModelKind::PartSize => Box::new(PartSize { n_qubits }),
+        }
+    }
+}
+
 struct UsedBoard {
     flags: FixedBitSet,
     n_edges: usize,
@@ -68,7 +169,7 @@ impl UsedBoard {
 }
 
 #[derive(Debug, Clone)]
-struct OrderInfo {
+pub(crate) struct OrderInfo {
     ordering: Vec<Order>,
     order_counts: [usize; 4],
     potential_wedges: Vec<(usize, Order, Order)>,
@@ -76,7 +177,7 @@ struct OrderInfo {
 }
 
 impl OrderInfo {
-    fn new(ordering: &[Order]) -> Self {
+    pub(crate) fn new(ordering: &[Order]) -> Self {
         let mut order_counts = [0; 4];
         for order in ordering {
             order_counts[*order as usize] += 1;
@@ -115,76 +216,207 @@ impl OrderInfo {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Record {
-    pub pattern: BitPattern,
+    pub pattern: PatternDescription,
     pub cutline: Cutline,
     pub cost: Cost,
+    /// Score of the cost under the cost model used in the search
+    pub score: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    /// The optimal records among the evaluated patterns
+    pub records: Vec<Record>,
+    /// Whether the search was cancelled or ran out of time before all the
+    /// patterns were evaluated
+    pub partial: bool,
+    /// Number of evaluated patterns
+    pub n_evaluated: usize,
+}
+
+/// Sort the records in the canonical order, by their patterns and then by
+/// the canonical keys of their cutlines.
+pub fn sort_records(records: &mut [Record]) {
+    records.sort_by_cached_key(|r| (r.pattern.clone(), r.cutline.canonical_key()));
+}
+
+/// Find the patterns whose minimum cost over all the cutlines is maximal,
+/// using the cost model selected in the `algorithm_config`.
+///
+/// The search stops scheduling patterns once `cancel` is cancelled or the
+/// time budget of the `algorithm_config` runs out, and returns the best
+/// records found so far. The records are sorted by [`sort_records`].
+/// Without cutline no pattern is evaluated and there is no record.
+pub fn max_min_cost<P: Pattern + Sync>(
+    graph: &SearchGraph,
+    patterns: Vec<P>,
+    cutlines: Vec<Cutline>,
+    algorithm_config: &AlgorithmConfig,
+    observer: &dyn ProgressObserver,
+    cancel: &CancelToken,
+) -> SearchResult {
+    let model = algorithm_config.cost_model.build(graph);
+    max_min_cost_with_model(
+        graph,
+        patterns,
+        cutlines,
+        algorithm_config,
+        model.as_ref(),
+        observer,
+        cancel,
+    )
 }
 
-pub fn max_min_cost(
+/// Same as [`max_min_cost`] but rank the cutlines with a custom cost model.
+pub fn max_min_cost_with_model<P: Pattern + Sync>(
     graph: &SearchGraph,
-    patterns: Vec<BitPattern>,
+    patterns: Vec<P>,
     cutlines: Vec<Cutline>,
     algorithm_config: &AlgorithmConfig,
-) -> Vec<Record> {
-    let ordering = algorithm_config.ordering.clone();
-    let order_info = OrderInfo::new(&ordering);
+    model: &dyn CostModel,
+    observer: &dyn ProgressObserver,
+    cancel: &CancelToken,
+) -> SearchResult {
     let cutlines_wrapped = cutlines
-        .clone()
         .into_iter()
         .map(|c| c.into_wrapped(graph))
         .collect_vec();
-    // progress bar
-    let n_tasks = patterns.len() as u64;
-    let pb = ProgressBar::new(n_tasks);
-    pb.set_style(
-        ProgressStyle::with_template(
-            "{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta})",
-        )
-        .unwrap()
-        .with_key("eta", |state: &ProgressState, w: &mut dyn Write| {
-            write!(w, "{:.1}s", state.eta().as_secs_f64()).unwrap()
-        })
-        .progress_chars("#>-"),
-    );
+    max_min_cost_wrapped(
+        graph,
+        &patterns,
+        &cutlines_wrapped,
+        algorithm_config,
+        model,
+        observer,
+        cancel,
+    )
+}
 
+/// Same as [`max_min_cost_with_model`] with the cutlines already wrapped.
+pub(crate) fn max_min_cost_wrapped<P: Pattern + Sync>(
+    graph: &SearchGraph,
+    patterns: &[P],
+    cutlines_wrapped: &[CutlineWrapped],
+    algorithm_config: &AlgorithmConfig,
+    model: &dyn CostModel,
+    observer: &dyn ProgressObserver,
+    cancel: &CancelToken,
+) -> SearchResult {
+    let deadline = algorithm_config
+        .time_budget
+        .map(|secs| Instant::now() + Duration::from_secs(secs));
+    if cutlines_wrapped.is_empty() {
+        return SearchResult {
+            records: Vec::new(),
+            partial: false,
+            n_evaluated: 0,
+        };
+    }
+    let order_info = OrderInfo::new(&algorithm_config.ordering);
+    let n_patterns = patterns.len();
+    observer.on_start(n_patterns, cutlines_wrapped.len());
+    // scores are non-negative, so their bits are ordered as the floats
+    let best_score = AtomicU64::new(0);
+    let completed = AtomicUsize::new(0);
     let costs: Vec<_> = patterns
-        .into_par_iter()
-        .progress_with(pb)
+        .par_iter()
         .map(|pattern| {
-            (
-                pattern.clone(),
-                calculate_min_cost(graph, pattern, &cutlines_wrapped, &order_info),
-            )
+            if cancel.is_cancelled() || deadline.is_some_and(|d| Instant::now() >= d) {
+                return None;
+            }
+            let order_vec = pattern.order_vec(graph);
+            // never `None`, the search without cutline returned early
+            let min_cost =
+                calculate_min_cost(graph, &order_vec, cutlines_wrapped, &order_info, model)?;
+            let best = best_score.fetch_max(min_cost.2.to_bits(), Ordering::Relaxed);
+            observer.on_pattern_completed(
+                completed.fetch_add(1, Ordering::Relaxed) + 1,
+                f64::from_bits(best).max(min_cost.2),
+            );
+            Some((pattern.describe(graph), min_cost))
         })
+        .while_some()
         .collect();
-    costs
+    observer.on_finish();
+    let n_evaluated = costs.len();
+    let mut records = costs
         .into_iter()
-        .max_set_by(|&(_, (_, c1)), &(_, (_, c2))| c1.cost().partial_cmp(&c2.cost()).unwrap())
+        .max_set_by(|(_, (_, _, s1)), (_, (_, _, s2))| s1.partial_cmp(s2).unwrap())
         .into_iter()
-        .map(|(pattern, (i, cost))| Record {
+        .map(|(pattern, (i, cost, score))| Record {
             pattern,
             cutline: Cutline::from_wrapper(cutlines_wrapped[i].clone(), graph),
             cost,
+            score,
+        })
+        .collect_vec();
+    sort_records(&mut records);
+    SearchResult {
+        records,
+        partial: n_evaluated < n_patterns,
+        n_evaluated,
+    }
+}
+
+/// The optimal cutline of a single pattern, `None` without cutline.
+pub(crate) fn min_cost_record<P: Pattern>(
+    graph: &SearchGraph,
+    pattern: &P,
+    cutlines_wrapped: &[CutlineWrapped],
+    algorithm_config: &AlgorithmConfig,
+    model: &dyn CostModel,
+) -> Option<Record> {
+    let order_info = OrderInfo::new(&algorithm_config.ordering);
+    let order_vec = pattern.order_vec(graph);
+    let (i, cost, score) =
+        calculate_min_cost(graph, &order_vec, cutlines_wrapped, &order_info, model)?;
+    Some(Record {
+        pattern: pattern.describe(graph),
+        cutline: Cutline::from_wrapper(cutlines_wrapped[i].clone(), graph),
+        cost,
+        score,
+    })
+}
+
+/// The optimal cutline of every pattern, as its index, cost and score,
+/// `None` without cutline.
+pub(crate) fn min_costs<P: Pattern + Sync>(
+    graph: &SearchGraph,
+    patterns: &[P],
+    cutlines_wrapped: &[CutlineWrapped],
+    algorithm_config: &AlgorithmConfig,
+    model: &dyn CostModel,
+) -> Option<Vec<(usize, Cost, f64)>> {
+    let order_info = OrderInfo::new(&algorithm_config.ordering);
+    patterns
+        .par_iter()
+        .map(|pattern| {
+            let order_vec = pattern.order_vec(graph);
+            calculate_min_cost(graph, &order_vec, cutlines_wrapped, &order_info, model)
         })
-        .collect_vec()
+        .collect()
 }
 
-fn calculate_min_cost(
+/// The cutline with the minimum cost for the orders, as its index, cost and
+/// score, `None` without cutline.
+pub(crate) fn calculate_min_cost(
     graph: &SearchGraph,
-    pattern: BitPattern,
+    order_vec: &[Option<Order>],
     cutlines: &[CutlineWrapped],
     order_info: &OrderInfo,
-) -> (usize, Cost) {
-    let order_vec = pattern.order_vec(graph);
+    model: &dyn CostModel,
+) -> Option<(usize, Cost, f64)> {
     let mut used_flags = UsedBoard::new(graph.primal.edge_count(), order_info.ordering.len());
     cutlines
         .iter()
-        .map(|cutline| cost_for_cutline(&order_vec, cutline, order_info, &mut used_flags))
         .enumerate()
-        .min_by(|&(_, c1), &(_, c2)| c1.cost().partial_cmp(&c2.cost()).unwrap())
-        .unwrap()
+        .map(|(i, cutline)| {
+            let cost = cost_for_cutline(order_vec, cutline, order_info, &mut used_flags);
+            (i, cost, model.score(&cost))
+        })
+        .min_by(|(_, _, s1), (_, _, s2)| s1.partial_cmp(s2).unwrap())
 }
 
 fn cost_for_cutline(
@@ -208,13 +440,11 @@ fn cost_for_cutline(
         potential_dcds,
     } = order_info;
 
-    // total two qubits gates on the cut
+    // total two qubits gates on the cut, a link without order carries none
     let length: usize = split
         .iter()
-        .map(|&i| {
-            let order = order_vec[i].unwrap();
-            order_counts[order as usize]
-        })
+        .filter_map(|&i| order_vec[i])
+        .map(|order| order_counts[order as usize])
         .sum();
 
     // Wedge fusion
@@ -222,7 +452,7 @@ fn cost_for_cutline(
     for &(i, order1, order2) in potential_wedges {
         for &(e1, e2) in wedge_candidates {
             for (e1, e2) in [(e1, e2), (e2, e1)] {
-                if order_vec[e1].unwrap() == order1 && order_vec[e2].unwrap() == order2 {
+                if order_vec[e1] == Some(order1) && order_vec[e2] == Some(order2) {
                     if !use_flags.is_used(i, e1) && !use_flags.is_used(i + 1, e2) {
                         use_flags.set_used(i, e1);
                         use_flags.set_used(i + 1, e2);
@@ -238,8 +468,8 @@ fn cost_for_cutline(
     let mut n_dcd: usize = 0;
     for &(i, order1, order2) in potential_dcds {
         for &(e1, e2) in dcd_candidates {
-            if order_vec[e1].unwrap() == order1
-                && order_vec[e2].unwrap() == order2
+            if order_vec[e1] == Some(order1)
+                && order_vec[e2] == Some(order2)
                 && !use_flags.is_used(i, e1)
                 && !use_flags.is_used(i + 2, e1)
                 && !use_flags.is_used(i + 1, e2)
@@ -261,7 +491,9 @@ fn cost_for_cutline(
     let end_order = *ordering.last().unwrap();
     let depth = ordering.len() - 1;
     for &e in split {
-        let order = order_vec[e].unwrap();
+        let Some(order) = order_vec[e] else {
+            continue;
+        };
         if order == start_order && !use_flags.is_used(0, e) {
             use_flags.set_used(0, e);
             start_end_elision += 1;
@@ -283,6 +515,238 @@ fn cost_for_cutline(
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cancel::CancelToken;
+    use crate::config::{AlgorithmConfigBuilder, TopologyConfigBuilder};
+    use crate::cutline::search_cutlines;
+    use crate::pattern::{pattern_from_repr, VecPattern};
+    use crate::progress::SilentProgress;
+    use crate::search_pattern::search_vec_patterns;
+    use crate::test_util::grid_search;
+
+    #[test]
+    fn test_cost_models() {
+        let cost = Cost {
+            gates: 24,
+            start_end: 2,
+            wedge: 3,
+            dcd: 2,
+            unbalance: 4,
+        };
+        assert_eq!(cost.cut_length(), 18.0);
+        assert_eq!(StandardCost.score(&cost), 4f64.powi(19) + 4f64.powi(17));
+        assert_eq!(PathCount.score(&cost), 4f64.powi(18));
+        let n_qubits = 60;
+        assert_eq!(
+            PathMemory { n_qubits }.score(&cost),
+            4f64.powi(18) * 2f64.powi(32)
+        );
+        // the standard cost is the part size cost normalized by 2^(n/2)
+        assert_eq!(
+            PartSize { n_qubits }.score(&cost),
+            StandardCost.score(&cost) * 2f64.powi(30)
+        );
+    }
+
+    #[test]
+    fn test_max_min_cost_of_coloring_patterns() {
+        let graph = SearchGraph::default();
+        let algo = AlgorithmConfigBuilder::default().build().unwrap();
+        let cutlines = search_cutlines(&graph, &algo).unwrap();
+        let bit_pattern = pattern_from_repr("1_0100110010_0_1001100101").unwrap();
+        let coloring: VecPattern = bit_pattern.order_vec(&graph);
+        let bit_records = max_min_cost(
+            &graph,
+            vec![bit_pattern],
+            cutlines.clone(),
+            &algo,
+            &SilentProgress,
+            &CancelToken::new(),
+        )
+        .records;
+        let vec_records = max_min_cost(
+            &graph,
+            vec![coloring.clone()],
+            cutlines,
+            &algo,
+            &SilentProgress,
+            &CancelToken::new(),
+        )
+        .records;
+        assert_eq!(bit_records.len(), 1);
+        assert_eq!(vec_records.len(), 1);
+        assert_eq!(
+            bit_records[0].pattern,
+            PatternDescription::Bit("1_0100110010_0_1001100101".parse().unwrap())
+        );
+        assert_eq!(
+            vec_records[0].pattern,
+            PatternDescription::Coloring(coloring)
+        );
+        assert_eq!(bit_records[0].cost, vec_records[0].cost);
+
+        let config = TopologyConfigBuilder::default()
+            .width(4)
+            .height(3)
+            .build()
+            .unwrap();
+        let graph = SearchGraph::from_config(config).unwrap();
+        let algo = AlgorithmConfigBuilder::default()
+            .min_depth(0)
+            .max_depth(4)
+            .build()
+            .unwrap();
+        let cutlines = search_cutlines(&graph, &algo).unwrap();
+        let records = max_min_cost(
+            &graph,
+            search_vec_patterns(&graph).collect(),
+            cutlines,
+            &algo,
+            &SilentProgress,
+            &CancelToken::new(),
+        )
+        .records;
+        assert!(!records.is_empty());
+        assert!(records
+            .iter()
+            .all(|r| matches!(r.pattern, PatternDescription::Coloring(_))));
+    }
+
+    #[derive(Default)]
+    struct CountingObserver {
+        n_patterns: AtomicUsize,
+        completed: AtomicUsize,
+        best_score: AtomicU64,
+    }
+
+    impl ProgressObserver for CountingObserver {
+        fn on_start(&self, n_patterns: usize, _n_cutlines: usize) {
+            self.n_patterns.store(n_patterns, Ordering::Relaxed);
+        }
+
+        fn on_pattern_completed(&self, completed: usize, best_score: f64) {
+            self.completed.fetch_max(completed, Ordering::Relaxed);
+            self.best_score
+                .fetch_max(best_score.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_progress_observer() {
+        let (graph, algo, cutlines) = grid_search(vec![]);
+        let patterns = crate::search_pattern::search_bit_patterns(&graph).collect_vec();
+        let n_patterns = patterns.len();
+        let observer = CountingObserver::default();
+        let records = max_min_cost(
+            &graph,
+            patterns,
+            cutlines,
+            &algo,
+            &observer,
+            &CancelToken::new(),
+        )
+        .records;
+        assert_eq!(observer.n_patterns.into_inner(), n_patterns);
+        assert_eq!(observer.completed.into_inner(), n_patterns);
+        assert_eq!(
+            f64::from_bits(observer.best_score.into_inner()),
+            records[0].score
+        );
+    }
+
+    #[test]
+    fn test_deterministic_records() {
+        let (graph, algo, cutlines) = grid_search(vec![]);
+        let patterns = crate::search_pattern::search_bit_patterns(&graph).collect_vec();
+        let run = |n_threads: usize, patterns: Vec<_>| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(n_threads)
+                .build()
+                .unwrap();
+            pool.install(|| {
+                max_min_cost(
+                    &graph,
+                    patterns,
+                    cutlines.clone(),
+                    &algo,
+                    &SilentProgress,
+                    &CancelToken::new(),
+                )
+                .records
+                .into_iter()
+                .map(|r| (r.pattern, r.cutline))
+                .collect_vec()
+            })
+        };
+        let records = run(1, patterns.clone());
+        assert!(records.len() > 1);
+        assert!(records.windows(2).all(|w| w[0].0 <= w[1].0));
+        let mut reversed = patterns;
+        reversed.reverse();
+        assert_eq!(records, run(4, reversed));
+    }
+
+    #[test]
+    fn test_search_without_cutlines() {
+        let (graph, algo, _) = grid_search(vec![]);
+        let patterns = crate::search_pattern::search_bit_patterns(&graph)
+            .take(8)
+            .collect_vec();
+        let result = max_min_cost(
+            &graph,
+            patterns.clone(),
+            Vec::new(),
+            &algo,
+            &SilentProgress,
+            &CancelToken::new(),
+        );
+        assert!(!result.partial);
+        assert_eq!(result.n_evaluated, 0);
+        assert!(result.records.is_empty());
+        let model = algo.cost_model.build(&graph);
+        assert!(min_cost_record(&graph, &patterns[0], &[], &algo, model.as_ref()).is_none());
+        assert!(min_costs(&graph, &patterns, &[], &algo, model.as_ref()).is_none());
+    }
+
+    #[test]
+    fn test_cancelled_search() {
+        let (graph, algo, cutlines) = grid_search(vec![]);
+        let patterns = crate::search_pattern::search_bit_patterns(&graph)
+            .take(64)
+            .collect_vec();
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let result = max_min_cost(
+            &graph,
+            patterns.clone(),
+            cutlines.clone(),
+            &algo,
+            &SilentProgress,
+            &cancel,
+        );
+        assert!(result.partial);
+        assert_eq!(result.n_evaluated, 0);
+        assert!(result.records.is_empty());
+
+        let algo = AlgorithmConfig {
+            time_budget: Some(3600),
+            ..algo
+        };
+        let result = max_min_cost(
+            &graph,
+            patterns,
+            cutlines,
+            &algo,
+            &SilentProgress,
+            &CancelToken::new(),
+        );
+        assert!(!result.partial);
+        assert_eq!(result.n_evaluated, 64);
+    }
+}
+
 // #[cfg(test)]
 // mod tests {
 //     use crate::{
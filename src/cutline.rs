@@ -1,22 +1,75 @@
 use crate::{
+    boundary::{clockwise_boundary, BoundaryRouter, Endpoints},
     config::AlgorithmConfig,
-    graph::{duality_map, CutGraph, Point, SearchGraph},
+    graph::{CutGraph, Point, SearchGraph},
+    modular::link_cutlines,
 };
 use itertools::Itertools;
 use petgraph::visit::{Dfs, EdgeFiltered, EdgeRef};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fmt;
 use std::iter::from_fn;
 
 pub type Path = Vec<Point>;
 pub type Edge = (Point, Point);
 type Split = Vec<Edge>;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// Errors of the cutline search on a [`SearchGraph`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CutlineError {
+    /// Positions of the endpoints beyond the clockwise boundary of the graph
+    EndpointsOutOfRange {
+        endpoints: Endpoints,
+        positions: Vec<usize>,
+        n_routers: usize,
+    },
+    /// A split of a graph with links leaves too many blocks of used qubits
+    /// to enumerate their sides, see [`modular`](crate::modular)
+    TooManyBlocks { n_blocks: usize, max_blocks: usize },
+    /// No cutline within the limits of the algorithm config, a
+    /// [`SearchSession`](crate::session::SearchSession) needs at least one
+    NoCutlines,
+}
+
+impl fmt::Display for CutlineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CutlineError::EndpointsOutOfRange {
+                endpoints,
+                positions,
+                n_routers,
+            } => write!(
+                f,
+                "The endpoints {} refer to positions {:?} beyond the {} boundary routers",
+                endpoints, positions, n_routers
+            ),
+            CutlineError::TooManyBlocks {
+                n_blocks,
+                max_blocks,
+            } => write!(
+                f,
+                "A cut leaves {} blocks of qubits joined by links, the sides of at most {} blocks can be enumerated",
+                n_blocks, max_blocks
+            ),
+            CutlineError::NoCutlines => write!(
+                f,
+                "No valid cutline within the depth, unbalance and endpoints limits"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CutlineError {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Cutline {
     pub split: Vec<Edge>,
     pub unbalance: usize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct CutlineWrapped {
     pub split: Vec<usize>,
     pub unbalance: usize,
@@ -25,6 +78,14 @@ pub struct CutlineWrapped {
 }
 
 impl Cutline {
+    /// The sorted split edges, which identify a cutline independent of the
+    /// direction it was found in.
+    pub fn canonical_key(&self) -> Vec<Edge> {
+        let mut key = self.split.clone();
+        key.sort();
+        key
+    }
+
     pub fn into_wrapped(self, graph: &SearchGraph) -> CutlineWrapped {
         let primal = &graph.primal;
         let split = self
@@ -32,8 +93,13 @@ impl Cutline {
             .into_iter()
             .filter(|e| primal.edge_weight(e.0, e.1).unwrap().to_owned())
             .collect_vec();
+        // the fusions are defined on nearest-neighbour couplers, the links
+        // take no part in them
+        let is_coupler = |(n1, n2): Edge| !graph.is_link(n1, n2);
         let wedge_candidates = split.iter().tuple_windows().filter_map(|(&e1, &e2)| {
-            if e1.0 == e2.0 || e1.0 == e2.1 || e1.1 == e2.0 || e1.1 == e2.1 {
+            if !is_coupler(e1) || !is_coupler(e2) {
+                None
+            } else if e1.0 == e2.0 || e1.0 == e2.1 || e1.1 == e2.0 || e1.1 == e2.1 {
                 Some((graph.edge_index(e1.0, e1.1), graph.edge_index(e2.0, e2.1)))
             } else {
                 None
@@ -41,12 +107,19 @@ impl Cutline {
         });
         let dcd_candidates = split
             .iter()
+            .filter(|&&e| is_coupler(e))
             .filter_map(|&(n1, n2)| {
                 let incident_node1 = (2 * n1.0 - n2.0, 2 * n1.1 - n2.1);
                 let incident_node2 = (2 * n2.0 - n1.0, 2 * n2.1 - n1.1);
+                let incident_weight = |n, incident| {
+                    primal
+                        .edge_weight(n, incident)
+                        .filter(|_| is_coupler((n, incident)))
+                        .copied()
+                };
                 match (
-                    primal.edge_weight(n1, incident_node1).copied(),
-                    primal.edge_weight(n2, incident_node2).copied(),
+                    incident_weight(n1, incident_node1),
+                    incident_weight(n2, incident_node2),
                 ) {
                     (Some(true), Some(false)) | (Some(true), None) => Some((
                         graph.edge_index(n1, n2),
@@ -91,27 +164,147 @@ impl Cutline {
     }
 }
 
-fn path_to_split(path: Path) -> Split {
+fn path_to_split(graph: &SearchGraph, path: Path) -> Split {
     path.iter()
         .tuple_windows()
-        .map(|(&n1, &n2)| {
-            let (n1, n2) = duality_map(n1, n2);
-            (n1.min(n2), n1.max(n2))
-        })
+        .filter_map(|(&n1, &n2)| graph.crossed_edge(n1, n2))
         .collect_vec()
 }
 
-pub fn search_cutlines(graph: &SearchGraph, algorithm_config: &AlgorithmConfig) -> Vec<Cutline> {
-    let splits = search_splits(graph, algorithm_config);
+/// Enumerate the cutlines of the graph within the depth, unbalance and
+/// endpoints limits of the algorithm config.
+///
+/// Fails if the endpoints refer to positions beyond the clockwise boundary
+/// of the graph, which may happen on a graph with defects, or if a graph
+/// with links has too many tiles, see [`CutlineError`].
+pub fn search_cutlines(
+    graph: &SearchGraph,
+    algorithm_config: &AlgorithmConfig,
+) -> Result<Vec<Cutline>, CutlineError> {
+    let splits = search_splits(graph, algorithm_config, None)?;
     let splits = dedup_virtual_dispatch(graph, splits);
     debug_assert!(splits.iter().unique().count() == splits.len());
+    if !graph.links.is_empty() {
+        return link_cutlines(graph, splits, algorithm_config.max_unbalance);
+    }
     let unused_qubits = &graph.unused_qubits;
     let mut used_qubits = graph.primal.nodes().collect_vec();
     used_qubits.retain(|q| !unused_qubits.contains(q));
-    limit_unbalance(graph, splits, algorithm_config.max_unbalance, &used_qubits)
+    Ok(
+        limit_unbalance(graph, splits, algorithm_config.max_unbalance, &used_qubits)
+            .into_iter()
+            .map_into()
+            .collect(),
+    )
+}
+
+/// The cutlines of [`search_cutlines`] crossing one of the couplers, on a
+/// graph without links.
+///
+/// The paths which can not reach the dual edge of one of the couplers within
+/// the depth limit are not explored, so that after a few couplers of the
+/// graph changed the cutlines made possible by the change are found without
+/// enumerating all of them again.
+pub(crate) fn search_cutlines_crossing(
+    graph: &SearchGraph,
+    algorithm_config: &AlgorithmConfig,
+    couplers: &[Edge],
+) -> Result<Vec<Cutline>, CutlineError> {
+    debug_assert!(graph.links.is_empty());
+    let through = Through::new(graph, couplers);
+    let mut splits = search_splits(graph, algorithm_config, Some(&through))?;
+    splits.retain(|split| split.iter().any(|c| couplers.contains(c)));
+    let splits = dedup_virtual_dispatch(graph, splits);
+    let unused_qubits = &graph.unused_qubits;
+    let mut used_qubits = graph.primal.nodes().collect_vec();
+    used_qubits.retain(|q| !unused_qubits.contains(q));
+    Ok(
+        limit_unbalance(graph, splits, algorithm_config.max_unbalance, &used_qubits)
+            .into_iter()
+            .map_into()
+            .collect(),
+    )
+}
+
+/// The dual edges crossing some couplers, with lower bounds of the depth a
+/// path needs from every dual node to end on the boundary, after crossing
+/// one of the dual edges or not.
+struct Through {
+    edges: HashSet<Edge>,
+    through_depths: HashMap<Point, usize>,
+    boundary_depths: HashMap<Point, usize>,
+}
+
+impl Through {
+    fn new(graph: &SearchGraph, couplers: &[Edge]) -> Self {
+        let edges: HashSet<Edge> = graph
+            .crossings
+            .iter()
+            .filter(|(d, c)| couplers.contains(c) && graph.dual.contains_edge(d.0, d.1))
+            .map(|(&d, _)| d)
+            .collect();
+        // the last edge of a path to the boundary does not count in its depth
+        let boundary_depths =
+            dual_depths(&graph.dual, graph.dual_boundaries.iter().map(|&n| (n, 0)))
+                .into_iter()
+                .map(|(n, depth)| (n, depth.saturating_sub(1)))
+                .collect::<HashMap<_, _>>();
+        let through_depths = dual_depths(
+            &graph.dual,
+            edges
+                .iter()
+                .flat_map(|&(d1, d2)| [d1, d2])
+                .filter_map(|n| boundary_depths.get(&n).map(|&depth| (n, depth))),
+        );
+        Self {
+            edges,
+            through_depths,
+            boundary_depths,
+        }
+    }
+
+    fn crosses(&self, n1: Point, n2: Point) -> bool {
+        self.edges.contains(&(n1.min(n2), n1.max(n2)))
+    }
+
+    /// Whether a path at the node, which has crossed one of the dual edges or
+    /// not, can still end on the boundary after crossing one within the
+    /// depth limit.
+    fn reachable(&self, node: Point, crossed: bool, depth: usize, max_path_length: usize) -> bool {
+        let depths = if crossed {
+            &self.boundary_depths
+        } else {
+            &self.through_depths
+        };
+        depths
+            .get(&node)
+            .is_some_and(|d| depth + d < max_path_length)
+    }
+}
+
+/// The minimum depths from the seeds, starting at their depth, to every
+/// node of the dual graph.
+fn dual_depths(
+    dual: &CutGraph,
+    seeds: impl IntoIterator<Item = (Point, usize)>,
+) -> HashMap<Point, usize> {
+    let mut depths: HashMap<Point, usize> = HashMap::new();
+    let mut heap: BinaryHeap<Reverse<(usize, Point)>> = seeds
         .into_iter()
-        .map_into()
-        .collect()
+        .map(|(n, depth)| Reverse((depth, n)))
+        .collect();
+    while let Some(Reverse((depth, n))) = heap.pop() {
+        if depths.contains_key(&n) {
+            continue;
+        }
+        depths.insert(n, depth);
+        for (_, m, &used) in dual.edges(n) {
+            if !depths.contains_key(&m) {
+                heap.push(Reverse((depth + used as usize, m)));
+            }
+        }
+    }
+    depths
 }
 
 fn dedup_virtual_dispatch(graph: &SearchGraph, splits: Vec<Split>) -> Vec<Split> {
@@ -130,7 +323,7 @@ fn limit_unbalance(
     graph: &SearchGraph,
     splits: Vec<Split>,
     max_unbalance: usize,
-    used_qubits: &Vec<Point>,
+    used_qubits: &[Point],
 ) -> Vec<Cutline> {
     splits
         .into_iter()
@@ -145,36 +338,82 @@ fn limit_unbalance(
         .collect()
 }
 
-fn search_splits(graph: &SearchGraph, algorithm_config: &AlgorithmConfig) -> Vec<Split> {
+fn search_splits(
+    graph: &SearchGraph,
+    algorithm_config: &AlgorithmConfig,
+    through: Option<&Through>,
+) -> Result<Vec<Split>, CutlineError> {
     let boundaries = graph.dual_boundaries.clone();
-    (0..boundaries.len() - 1)
+    // the clockwise positions of the routers, to restrict the endpoints
+    let clockwise: Option<HashMap<Point, (usize, BoundaryRouter)>> =
+        match algorithm_config.endpoints {
+            Some(endpoints) => {
+                let routers = clockwise_boundary(graph);
+                let positions = endpoints.out_of_range(routers.len());
+                if !positions.is_empty() {
+                    return Err(CutlineError::EndpointsOutOfRange {
+                        endpoints,
+                        positions,
+                        n_routers: routers.len(),
+                    });
+                }
+                let clockwise = routers
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, router)| (router.node, (i, router)))
+                    .collect();
+                Some(clockwise)
+            }
+            None => None,
+        };
+    let connects = |n1: &Point, n2: &Point| match (&algorithm_config.endpoints, &clockwise) {
+        (Some(endpoints), Some(clockwise)) => {
+            let (i1, r1) = &clockwise[n1];
+            let (i2, r2) = &clockwise[n2];
+            endpoints.connects((*i1, r1), (*i2, r2))
+        }
+        _ => true,
+    };
+    let splits = (0..boundaries.len() - 1)
         .flat_map(|i| {
             let from = boundaries[i];
-            let tos = boundaries[i + 1..].to_owned();
+            let tos = boundaries[i + 1..]
+                .iter()
+                .filter(|to| connects(&from, to))
+                .copied()
+                .collect_vec();
+            if tos.is_empty() {
+                return Vec::new();
+            }
             search_paths_between(
                 graph,
                 from,
                 tos,
                 algorithm_config.min_depth,
                 algorithm_config.max_depth,
+                through,
             )
-            .map(path_to_split)
+            .map(|path| path_to_split(graph, path))
             .collect_vec()
         })
-        .collect()
+        .collect();
+    Ok(splits)
 }
 
-fn search_paths_between(
-    graph: &SearchGraph,
+fn search_paths_between<'a>(
+    graph: &'a SearchGraph,
     from: Point,
     tos: Vec<Point>,
     min_path_length: usize,
     max_path_length: usize,
-) -> impl Iterator<Item = Path> + '_ {
+    through: Option<&'a Through>,
+) -> impl Iterator<Item = Path> + 'a {
     let boundaries = &graph.dual_boundaries;
     let graph = &graph.dual;
     // list of visited nodes
     let mut visited: Vec<Point> = vec![from];
+    // whether the path up to each visited node crosses one of the through edges
+    let mut crossed = vec![false];
     // list of childs of currently exploring path nodes,
     // last elem is list of childs of last visited node
     let mut stack = vec![graph.neighbors(from)];
@@ -190,8 +429,17 @@ fn search_paths_between(
                             return Some(path);
                         }
                     } else if !boundaries.contains(&child) && !visited.contains(&child) {
-                        visited.push(child);
-                        stack.push(graph.neighbors(child));
+                        let parent = *visited.last().unwrap();
+                        let child_crossed = crossed.last().copied().unwrap_or_default()
+                            || through.is_some_and(|t| t.crosses(parent, child));
+                        let child_depth = depth + compute_depth(graph, &[parent, child]);
+                        if through.is_none_or(|t| {
+                            t.reachable(child, child_crossed, child_depth, max_path_length)
+                        }) {
+                            visited.push(child);
+                            crossed.push(child_crossed);
+                            stack.push(graph.neighbors(child));
+                        }
                     }
                 } else {
                     if let Some(c) = Some(child)
@@ -204,17 +452,65 @@ fn search_paths_between(
                     }
                     stack.pop();
                     visited.pop();
+                    crossed.pop();
                 }
             } else {
                 stack.pop();
                 visited.pop();
+                crossed.pop();
             }
         }
         None
     })
 }
 
-fn compute_unbalance(graph: &SearchGraph, used_qubits: &Vec<Point>, split: &Split) -> usize {
+/// Indices of the qubits (in the order of `graph.primal.nodes()`) lying on
+/// one side of the split.
+pub fn split_part(split: &[Edge], graph: &SearchGraph) -> Vec<usize> {
+    let node_map: HashMap<_, _> = graph
+        .primal
+        .nodes()
+        .enumerate()
+        .map(|(i, n)| (n, i))
+        .collect();
+    let filtered_graph = EdgeFiltered::from_fn(&graph.primal, |e| {
+        let (source, target) = (e.source(), e.target());
+        !split.contains(&(source.min(target), source.max(target))) && *e.weight()
+    });
+    let mut dfs = Dfs::new(&filtered_graph, graph.primal.nodes().nth(1).unwrap());
+    let mut part = Vec::new();
+    while let Some(qubit) = dfs.next(&filtered_graph) {
+        part.push(node_map[&qubit]);
+    }
+    part
+}
+
+/// The two parts of the used qubits split by the cutline, the first part
+/// contains the first used qubit of the graph.
+pub fn split_parts(split: &[Edge], graph: &SearchGraph) -> (Vec<Point>, Vec<Point>) {
+    let unused_qubits = &graph.unused_qubits;
+    let filtered_graph = EdgeFiltered::from_fn(&graph.primal, |e| {
+        let (source, target) = (e.source(), e.target());
+        *e.weight() && !split.contains(&(source.min(target), source.max(target)))
+    });
+    let mut used_qubits = graph.primal.nodes().collect_vec();
+    used_qubits.retain(|q| !unused_qubits.contains(q));
+    let mut dfs = Dfs::new(&filtered_graph, used_qubits[0]);
+    let mut part0 = Vec::new();
+    while let Some(qubit) = dfs.next(&filtered_graph) {
+        part0.push(qubit);
+    }
+    used_qubits.retain(|q| !part0.contains(q));
+    part0.sort_by_key(|&(x, y)| (y, x));
+    used_qubits.sort_by_key(|&(x, y)| (y, x));
+    (part0, used_qubits)
+}
+
+pub(crate) fn compute_unbalance(
+    graph: &SearchGraph,
+    used_qubits: &[Point],
+    split: &Split,
+) -> usize {
     let filtered_graph = EdgeFiltered::from_fn(&graph.primal, |e| {
         let (source, target) = (e.source(), e.target());
         *e.weight() && !split.contains(&(source.min(target), source.max(target)))
@@ -222,7 +518,8 @@ fn compute_unbalance(graph: &SearchGraph, used_qubits: &Vec<Point>, split: &Spli
     let mut dfs = Dfs::new(&filtered_graph, used_qubits[0]);
     let mut count = 0;
     while let Some(qubit) = dfs.next(&filtered_graph) {
-        if used_qubits.contains(&qubit) {
+        // the unused qubits are much fewer than the used ones
+        if !graph.unused_qubits.contains(&qubit) {
             count += 1;
         }
     }
@@ -0,0 +1,188 @@
+use crate::graph::Point;
+use crate::layout::{on_segment, orientation};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Dead qubits or couplers given by the coordinates of the qubits.
+///
+/// The coordinates are the grid points of the rotated grid, or the qubit
+/// coordinates of a loaded [`Layout`](crate::layout::Layout). The JSON form
+/// names the kind of the defect:
+///
+/// ```json
+/// [
+///   {"qubit": [3, 0]},
+///   {"coupler": [[0, 1], [1, 2]]},
+///   {"rect": {"min": [4, 4], "max": [6, 6]}},
+///   {"polygon": [[8, 0], [11, 0], [11, 3]]}
+/// ]
+/// ```
+///
+/// The text form used by the CLI is `x,y` for a qubit, `x1,y1-x2,y2` for a
+/// coupler, `rect:x1,y1:x2,y2` for the qubits in a rectangle and
+/// `polygon:x1,y1:x2,y2:...` for the qubits in a polygon, the coordinates
+/// may also be written as `(x, y)`. The qubits on the border of a region
+/// are part of it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Defect {
+    Qubit(Point),
+    Coupler(Point, Point),
+    Rect { min: Point, max: Point },
+    Polygon(Vec<Point>),
+}
+
+impl Defect {
+    /// Whether the region of the defect holds the point, a coupler holds
+    /// none.
+    pub fn contains(&self, p: Point) -> bool {
+        match self {
+            Defect::Qubit(q) => *q == p,
+            Defect::Coupler(..) => false,
+            Defect::Rect { min, max } => {
+                p.0 >= min.0 && p.0 <= max.0 && p.1 >= min.1 && p.1 <= max.1
+            }
+            Defect::Polygon(vertices) => in_polygon(vertices, p),
+        }
+    }
+}
+
+/// Whether the point lies inside or on the border of the polygon.
+fn in_polygon(vertices: &[Point], p: Point) -> bool {
+    let sides = vertices.iter().copied().circular_tuple_windows::<(_, _)>();
+    if vertices.len() == 1 {
+        return vertices[0] == p;
+    }
+    if sides
+        .clone()
+        .any(|(a, b)| orientation(a, b, p) == 0 && on_segment(a, b, p))
+    {
+        return true;
+    }
+    // count the sides crossed by the ray from the point towards +x
+    sides
+        .filter(|&(a, b)| (a.1 > p.1) != (b.1 > p.1))
+        .filter(|&(a, b)| {
+            let x = a.0 as f64 + (p.1 - a.1) as f64 * (b.0 - a.0) as f64 / (b.1 - a.1) as f64;
+            (p.0 as f64) < x
+        })
+        .count()
+        % 2
+        == 1
+}
+
+impl fmt::Display for Defect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let point = |p: &Point| format!("{},{}", p.0, p.1);
+        match self {
+            Defect::Qubit(p) => write!(f, "{}", point(p)),
+            Defect::Coupler(p1, p2) => write!(f, "{}-{}", point(p1), point(p2)),
+            Defect::Rect { min, max } => write!(f, "rect:{}:{}", point(min), point(max)),
+            Defect::Polygon(vertices) => {
+                write!(f, "polygon:{}", vertices.iter().map(point).join(":"))
+            }
+        }
+    }
+}
+
+impl FromStr for Defect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid defect '{}', expected 'x,y', 'x1,y1-x2,y2', 'rect:x1,y1:x2,y2' or 'polygon:x1,y1:x2,y2:...'",
+                s
+            )
+        };
+        let parse_point = |p: &str| -> Result<Point, String> {
+            let p = p.trim();
+            let p = p
+                .strip_prefix('(')
+                .and_then(|p| p.strip_suffix(')'))
+                .unwrap_or(p);
+            let (x, y) = p.split_once(',').ok_or_else(invalid)?;
+            let x = x.trim().parse().map_err(|_| invalid())?;
+            let y = y.trim().parse().map_err(|_| invalid())?;
+            Ok((x, y))
+        };
+        let s = s.trim();
+        if let Some(rest) = s.strip_prefix("rect:") {
+            let (p1, p2) = rest.split_once(':').ok_or_else(invalid)?;
+            let (p1, p2) = (parse_point(p1)?, parse_point(p2)?);
+            let min = (p1.0.min(p2.0), p1.1.min(p2.1));
+            let max = (p1.0.max(p2.0), p1.1.max(p2.1));
+            Ok(Defect::Rect { min, max })
+        } else if let Some(rest) = s.strip_prefix("polygon:") {
+            let vertices = rest.split(':').map(parse_point).collect::<Result<_, _>>()?;
+            Ok(Defect::Polygon(vertices))
+        } else if let Some(i) = coupler_separator(s) {
+            Ok(Defect::Coupler(
+                parse_point(&s[..i])?,
+                parse_point(&s[i + 1..])?,
+            ))
+        } else {
+            Ok(Defect::Qubit(parse_point(s)?))
+        }
+    }
+}
+
+/// Position of the `-` between the two qubits of a coupler, which unlike a
+/// minus sign follows a number or a closing parenthesis.
+fn coupler_separator(s: &str) -> Option<usize> {
+    s.match_indices('-').map(|(i, _)| i).find(|&i| {
+        s[..i]
+            .trim_end()
+            .ends_with(|c: char| c.is_ascii_digit() || c == ')')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_defect() {
+        for (s, defect) in [
+            ("3,0", Defect::Qubit((3, 0))),
+            ("(3, 0)", Defect::Qubit((3, 0))),
+            ("0,1-1,2", Defect::Coupler((0, 1), (1, 2))),
+            ("(0, 1) - (1, 2)", Defect::Coupler((0, 1), (1, 2))),
+            ("-1,0--1,1", Defect::Coupler((-1, 0), (-1, 1))),
+            (
+                "rect:6,6:4,4",
+                Defect::Rect {
+                    min: (4, 4),
+                    max: (6, 6),
+                },
+            ),
+            (
+                "polygon:8,0:11,0:11,3",
+                Defect::Polygon(vec![(8, 0), (11, 0), (11, 3)]),
+            ),
+        ] {
+            assert_eq!(s.parse::<Defect>(), Ok(defect.clone()));
+            assert_eq!(defect.to_string().parse::<Defect>(), Ok(defect));
+        }
+        assert!("3".parse::<Defect>().is_err());
+        assert!("rect:1,1".parse::<Defect>().is_err());
+    }
+
+    #[test]
+    fn test_defect_region() {
+        let triangle = Defect::Polygon(vec![(0, 0), (4, 0), (0, 4)]);
+        assert!(triangle.contains((0, 0)));
+        assert!(triangle.contains((2, 2)));
+        assert!(triangle.contains((1, 1)));
+        assert!(!triangle.contains((3, 2)));
+        assert!(!triangle.contains((-1, 0)));
+        let rect = Defect::Rect {
+            min: (1, 1),
+            max: (2, 3),
+        };
+        assert!(rect.contains((2, 3)));
+        assert!(!rect.contains((3, 3)));
+    }
+}
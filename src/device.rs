@@ -0,0 +1,261 @@
+use crate::config::{TopologyConfig, TopologyConfigBuilder};
+use crate::graph::{grid_qubits, GraphError, Point};
+use crate::input::{InputError, InputFile};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// A qubit of a Cirq-style device, named `q(row, col)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct GridQubit {
+    pub row: i32,
+    pub col: i32,
+}
+
+impl GridQubit {
+    pub fn new(row: i32, col: i32) -> Self {
+        Self { row, col }
+    }
+
+    fn is_neighbor(&self, other: &GridQubit) -> bool {
+        (self.row - other.row).abs() + (self.col - other.col).abs() == 1
+    }
+}
+
+impl fmt::Display for GridQubit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "q({}, {})", self.row, self.col)
+    }
+}
+
+impl FromStr for GridQubit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid qubit name '{}', expected 'q(row, col)'", s);
+        let inner = s
+            .trim()
+            .strip_prefix("q(")
+            .and_then(|s| s.strip_suffix(')'))
+            .ok_or_else(invalid)?;
+        let (row, col) = inner.split_once(',').ok_or_else(invalid)?;
+        let row = row.trim().parse().map_err(|_| invalid())?;
+        let col = col.trim().parse().map_err(|_| invalid())?;
+        Ok(Self { row, col })
+    }
+}
+
+impl TryFrom<String> for GridQubit {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<GridQubit> for String {
+    fn from(q: GridQubit) -> Self {
+        q.to_string()
+    }
+}
+
+/// A device given by its `GridQubit`s and the couplers between them.
+///
+/// The couplers connect qubits in the same row or column, the rotated grid
+/// behind [`SearchGraph`](crate::graph::SearchGraph) is this lattice turned
+/// by 45 degrees. Without couplers every pair of neighbouring qubits is
+/// assumed to be coupled.
+///
+/// The JSON form lists the qubit names:
+///
+/// ```json
+/// {
+///   "qubits": ["q(0, 0)", "q(0, 1)", "q(1, 0)"],
+///   "couplers": [["q(0, 0)", "q(0, 1)"], ["q(0, 0)", "q(1, 0)"]]
+/// }
+/// ```
+///
+/// The text form has a qubit or a coupler on each line, empty lines and
+/// lines starting with `#` are skipped:
+///
+/// ```text
+/// q(0, 0)
+/// q(0, 1)
+/// q(1, 0)
+/// q(0, 0) - q(0, 1)
+/// q(0, 0) - q(1, 0)
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GridDevice {
+    pub qubits: Vec<GridQubit>,
+    #[serde(default)]
+    pub couplers: Option<Vec<(GridQubit, GridQubit)>>,
+}
+
+impl GridDevice {
+    /// Load a device description, JSON if the file has a `.json` extension
+    /// and the text form otherwise.
+    pub fn from_file(path: &Path) -> Result<Self, InputError> {
+        let device_error = |message: String| InputError::new(InputFile::Device, path, message);
+        let content = std::fs::read_to_string(path).map_err(|e| device_error(e.to_string()))?;
+        if path.extension().is_some_and(|e| e == "json") {
+            serde_json::from_str(&content).map_err(|e| device_error(e.to_string()))
+        } else {
+            Self::from_text(&content).map_err(device_error)
+        }
+    }
+
+    pub fn from_text(content: &str) -> Result<Self, String> {
+        let mut qubits = Vec::new();
+        let mut couplers = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let names = line
+                .match_indices("q(")
+                .map(|(start, _)| match line[start..].find(')') {
+                    Some(end) => line[start..=start + end].parse::<GridQubit>(),
+                    None => Err(format!("unclosed qubit name '{}'", &line[start..])),
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("line {}: {}", i + 1, e))?;
+            match names[..] {
+                [q] => qubits.push(q),
+                [q1, q2] => couplers.push((q1, q2)),
+                _ => {
+                    return Err(format!(
+                        "line {}: expected a qubit or a coupler, found '{}'",
+                        i + 1,
+                        line
+                    ))
+                }
+            }
+        }
+        let couplers = (!couplers.is_empty()).then_some(couplers);
+        Ok(Self { qubits, couplers })
+    }
+
+    /// Map the device onto the smallest rotated grid holding it. The qubits
+    /// and couplers of the grid missing from the device are marked unused.
+    pub fn to_topology(&self) -> Result<TopologyConfig, GraphError> {
+        let invalid = |message: String| Err(GraphError::InvalidDevice { message });
+        if self.qubits.is_empty() {
+            return invalid("the device has no qubit".to_string());
+        }
+        if let Some(q) = self.qubits.iter().duplicates().next() {
+            return invalid(format!("qubit {} is listed more than once", q));
+        }
+        let qubits: HashSet<_> = self.qubits.iter().copied().collect();
+        let mut couplers = HashSet::new();
+        for &(q1, q2) in self.couplers.iter().flatten() {
+            if let Some(q) = [q1, q2].into_iter().find(|q| !qubits.contains(q)) {
+                return invalid(format!("coupler {} - {} uses unlisted qubit {}", q1, q2, q));
+            }
+            if !q1.is_neighbor(&q2) {
+                return invalid(format!(
+                    "coupler {} - {} does not connect neighbouring qubits",
+                    q1, q2
+                ));
+            }
+            couplers.insert((q1.min(q2), q1.max(q2)));
+        }
+
+        // q(row, col + 1) is the lower right neighbour of q(row, col) on the
+        // rotated grid and q(row + 1, col) its lower left neighbour
+        let x_offset = self.qubits.iter().map(|q| q.col - q.row).min().unwrap();
+        let y_offset = self.qubits.iter().map(|q| q.col + q.row).min().unwrap();
+        let position =
+            |q: &GridQubit| -> Point { (q.col - q.row - x_offset, q.col + q.row - y_offset) };
+        let width = self.qubits.iter().map(|q| position(q).0 + 1).max().unwrap() as u32;
+        let height = self.qubits.iter().map(|q| position(q).1 + 1).max().unwrap() as u32;
+        let qubit_at_origin = (x_offset + y_offset).rem_euclid(2) == 0;
+
+        let grid = grid_qubits(width, height, qubit_at_origin);
+        let used: HashSet<_> = self.qubits.iter().map(position).collect();
+        let unused_qubits = grid
+            .iter()
+            .filter(|(p, _)| !used.contains(p))
+            .map(|(_, &i)| i)
+            .collect_vec();
+        let mut unused_couplers = Vec::new();
+        if self.couplers.is_some() {
+            for &q in &self.qubits {
+                for n in [
+                    GridQubit::new(q.row, q.col + 1),
+                    GridQubit::new(q.row + 1, q.col),
+                ] {
+                    if qubits.contains(&n) && !couplers.contains(&(q, n)) {
+                        let (i1, i2) = (grid[&position(&q)], grid[&position(&n)]);
+                        unused_couplers.push((i1.min(i2), i1.max(i2)));
+                    }
+                }
+            }
+            unused_couplers.sort();
+        }
+        Ok(TopologyConfigBuilder::default()
+            .width(width)
+            .height(height)
+            .qubit_at_origin(qubit_at_origin)
+            .unused_qubits(unused_qubits)
+            .unused_couplers(unused_couplers)
+            .build()
+            .unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::SearchGraph;
+
+    #[test]
+    fn test_grid_device_to_topology() {
+        // a 3x3 square lattice without q(0, 0) and the coupler q(1, 1) - q(1, 2)
+        let mut text = String::from("# 3x3 device\n");
+        for (row, col) in (0..3).cartesian_product(0..3).skip(1) {
+            text.push_str(&format!("q({}, {})\n", row, col));
+        }
+        for (row, col) in (0..3).cartesian_product(0..3) {
+            if row + 1 < 3 && (row, col) != (0, 0) {
+                text.push_str(&format!("q({}, {}) - q({}, {})\n", row, col, row + 1, col));
+            }
+            if col + 1 < 3 && (row, col) != (0, 0) && (row, col) != (1, 1) {
+                text.push_str(&format!("q({}, {}) - q({}, {})\n", row, col, row, col + 1));
+            }
+        }
+        let device = GridDevice::from_text(&text).unwrap();
+        assert_eq!(device.qubits.len(), 8);
+        assert_eq!(device.couplers.as_ref().unwrap().len(), 9);
+
+        let topo = device.to_topology().unwrap();
+        assert_eq!((topo.width, topo.height), (5, 4));
+        assert!(!topo.qubit_at_origin);
+        // the lower corners of the grid lie outside the device
+        assert_eq!(topo.unused_qubits, vec![7, 9]);
+        assert_eq!(topo.unused_couplers, vec![(3, 6)]);
+        let graph = SearchGraph::from_config(topo).unwrap();
+        assert_eq!(graph.primal.all_edges().filter(|e| *e.2).count(), 9);
+
+        let json = serde_json::to_string(&device).unwrap();
+        assert!(json.contains("\"q(0, 1)\""));
+        let parsed: GridDevice = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, device);
+
+        let mut invalid = device;
+        invalid
+            .couplers
+            .as_mut()
+            .unwrap()
+            .push((GridQubit::new(1, 1), GridQubit::new(2, 2)));
+        assert!(matches!(
+            invalid.to_topology(),
+            Err(GraphError::InvalidDevice { .. })
+        ));
+    }
+}
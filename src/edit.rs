@@ -0,0 +1,511 @@
+//! Incremental edits of the topology of a [`SearchGraph`].
+//!
+//! [`SearchGraph::apply_edits`] kills or revives qubits and couplers without
+//! building the graph again. The used flags of the primal graph are derived
+//! from the edited config like [`SearchGraph::from_config`] does, which is
+//! cheap, and only the dual edges crossing the couplers whose flag changed
+//! are updated. The boundary is contracted again from its seeds only when one
+//! of these dual edges touches it, and the dangling routers are removed from
+//! or restored to the dual graph accordingly. The edited graph is the same as
+//! the graph built from its updated config.
+//!
+//! The returned [`GraphUpdate`] tells which of the cutlines enumerated
+//! before the edits are still found on the edited graph, see
+//! [`GraphUpdate::valid_cutlines`].
+use crate::boundary::{clockwise_boundary, BoundaryRouter};
+use crate::config::{AlgorithmConfig, TopologyConfig};
+use crate::cutline::{compute_unbalance, Cutline, Edge};
+use crate::defect::Defect;
+use crate::graph::{
+    contract_boundary, mark_unused, split_dangling, CutGraph, GraphError, Point, SearchGraph,
+};
+use crate::modular::link_cut_unbalance;
+use indexmap::IndexMap;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Kill or revive the qubits or the coupler of a defect.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Edit {
+    /// Add the defect to the config
+    Kill(Defect),
+    /// Remove the qubits or the coupler of the defect from the unused
+    /// qubits, unused couplers and defects of the config. A qubit under
+    /// another region defect can not be revived.
+    Revive(Defect),
+}
+
+/// What changed in a [`SearchGraph`] with a list of edits.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GraphUpdate {
+    /// The qubits which became unused or used
+    pub qubits: Vec<Point>,
+    /// The couplers which became unused or used
+    pub couplers: Vec<Edge>,
+    /// The dual nodes which joined or left the boundary, or were removed
+    /// from or restored to the dual graph as dangling routers
+    pub dual_nodes: Vec<Point>,
+}
+
+impl GraphUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.qubits.is_empty() && self.couplers.is_empty() && self.dual_nodes.is_empty()
+    }
+
+    /// The cutlines enumerated before the edits which are still found by
+    /// [`search_cutlines`](crate::cutline::search_cutlines) on the edited
+    /// graph with the same split, with their index in `cutlines` and their
+    /// unbalance on the edited graph.
+    ///
+    /// A cutline stays valid when none of the couplers it crosses changed,
+    /// none of the dual nodes of its path joined or left the boundary, and
+    /// its unbalance is still within the limit. The endpoints restriction of
+    /// the algorithm is checked again on the edited boundary. A cutline
+    /// crossing links must also still split the used qubits into two sides,
+    /// see [`modular`](crate::modular). The cutlines
+    /// made possible by the edits are not listed, only a new search finds
+    /// them.
+    pub fn valid_cutlines(
+        &self,
+        graph: &SearchGraph,
+        cutlines: &[Cutline],
+        algorithm: &AlgorithmConfig,
+    ) -> Vec<(usize, Cutline)> {
+        let used_qubits = graph
+            .primal
+            .nodes()
+            .filter(|q| !graph.unused_qubits.contains(q))
+            .collect_vec();
+        let couplers: HashSet<&Edge> = self.couplers.iter().collect();
+        let dual_nodes: HashSet<&Point> = self.dual_nodes.iter().collect();
+        let crossing_of: HashMap<Edge, Edge> = graph
+            .crossings
+            .iter()
+            .map(|(&dual_edge, &coupler)| (coupler, dual_edge))
+            .collect();
+        let clockwise: HashMap<Point, (usize, BoundaryRouter)> = clockwise_boundary(graph)
+            .into_iter()
+            .enumerate()
+            .map(|(i, router)| (router.node, (i, router)))
+            .collect();
+        cutlines
+            .iter()
+            .enumerate()
+            .filter(|(_, cutline)| cutline.split.iter().all(|c| !couplers.contains(c)))
+            .filter_map(|(i, cutline)| {
+                let path = path_nodes(graph, &crossing_of, cutline);
+                if path.iter().any(|n| dual_nodes.contains(n)) {
+                    return None;
+                }
+                if let Some(endpoints) = algorithm.endpoints.filter(|_| !path.is_empty()) {
+                    let (r1, r2) = path
+                        .iter()
+                        .filter_map(|n| clockwise.get(n))
+                        .collect_tuple()?;
+                    if !endpoints.connects((r1.0, &r1.1), (r2.0, &r2.1)) {
+                        return None;
+                    }
+                }
+                let unbalance = if graph.links.is_empty() {
+                    compute_unbalance(graph, &used_qubits, &cutline.split)
+                } else {
+                    link_cut_unbalance(graph, &cutline.split)?
+                };
+                (unbalance <= algorithm.max_unbalance).then(|| {
+                    let split = cutline.split.clone();
+                    (i, Cutline { split, unbalance })
+                })
+            })
+            .collect()
+    }
+}
+
+/// The dual nodes of the path of a cutline, the ends of the dual edges
+/// crossing its couplers and the faces behind their midpoints. The links
+/// have no dual edge.
+fn path_nodes(
+    graph: &SearchGraph,
+    crossing_of: &HashMap<Edge, Edge>,
+    cutline: &Cutline,
+) -> Vec<Point> {
+    let ends = cutline
+        .split
+        .iter()
+        .filter_map(|c| crossing_of.get(c))
+        .flat_map(|&(d1, d2)| [d1, d2])
+        .unique()
+        .collect_vec();
+    // a midpoint has a crossing and a virtual dual edge
+    let behind_midpoints = ends.iter().filter_map(|&n| {
+        let (real, virtual_): (Vec<_>, Vec<_>) = graph
+            .full_dual
+            .neighbors(n)
+            .partition(|&m| graph.crossed_edge(n, m).is_some());
+        (real.len() == 1 && virtual_.len() == 1).then(|| virtual_[0])
+    });
+    behind_midpoints
+        .chain(ends.iter().copied())
+        .unique()
+        .collect()
+}
+
+impl SearchGraph {
+    /// Apply the edits to the config of the graph and update the graph in
+    /// place. On error, e.g. when the edits disconnect the used qubits, the
+    /// graph is left unchanged.
+    pub fn apply_edits(&mut self, edits: &[Edit]) -> Result<GraphUpdate, GraphError> {
+        let qubits_map: IndexMap<Point, u32> = self
+            .primal
+            .nodes()
+            .enumerate()
+            .map(|(i, q)| (q, i as u32))
+            .collect();
+        let mut config = self.config.clone();
+        for edit in edits {
+            match edit {
+                Edit::Kill(defect) => {
+                    if !config.defects.contains(defect) {
+                        config.defects.push(defect.clone());
+                    }
+                }
+                Edit::Revive(defect) => revive(&self.primal, &mut config, &qubits_map, defect)?,
+            }
+        }
+        let mut primal = self.primal.clone();
+        primal.all_edges_mut().for_each(|(_, _, used)| *used = true);
+        let (unused_qubits, dropped_islands) = mark_unused(&mut primal, &qubits_map, &config)?;
+
+        let qubits = primal
+            .nodes()
+            .filter(|q| self.unused_qubits.contains(q) != unused_qubits.contains(q))
+            .collect_vec();
+        let couplers = primal
+            .all_edges()
+            .filter(|&(n1, n2, used)| self.primal.edge_weight(n1, n2) != Some(used))
+            .map(|(n1, n2, _)| (n1.min(n2), n1.max(n2)))
+            .collect_vec();
+        self.config = config;
+        self.primal = primal;
+        self.unused_qubits = unused_qubits;
+        self.dropped_islands = dropped_islands;
+        let dual_nodes = self.update_dual(&couplers);
+        Ok(GraphUpdate {
+            qubits,
+            couplers,
+            dual_nodes,
+        })
+    }
+
+    /// Kill the qubits or the coupler of the defect, see [`Edit::Kill`].
+    pub fn kill(&mut self, defect: Defect) -> Result<GraphUpdate, GraphError> {
+        self.apply_edits(&[Edit::Kill(defect)])
+    }
+
+    /// Revive the qubits or the coupler of the defect, see [`Edit::Revive`].
+    pub fn revive(&mut self, defect: Defect) -> Result<GraphUpdate, GraphError> {
+        self.apply_edits(&[Edit::Revive(defect)])
+    }
+
+    /// Update the weights of the dual edges crossing the changed couplers,
+    /// then the boundary and the dangling routers if the boundary is
+    /// touched. Returns the dual nodes which joined or left the boundary or
+    /// the dual graph.
+    fn update_dual(&mut self, couplers: &[Edge]) -> Vec<Point> {
+        let couplers: HashSet<&Edge> = couplers.iter().collect();
+        let mut touched = HashSet::new();
+        for (&(d1, d2), coupler) in &self.crossings {
+            if !couplers.contains(coupler) {
+                continue;
+            }
+            let used = *self.primal.edge_weight(coupler.0, coupler.1).unwrap();
+            *self.full_dual.edge_weight_mut(d1, d2).unwrap() = used;
+            if let Some(weight) = self.dual.edge_weight_mut(d1, d2) {
+                *weight = used;
+            }
+            touched.extend([d1, d2]);
+        }
+        let old_boundary = self
+            .dual_boundaries
+            .iter()
+            .chain(&self.dangling_boundaries)
+            .copied()
+            .collect::<HashSet<_>>();
+        if !touched.iter().any(|n| old_boundary.contains(n)) {
+            return Vec::new();
+        }
+
+        let (dual_boundaries, dangling_boundaries) = split_dangling(
+            &self.full_dual,
+            contract_boundary(&self.boundary_seeds, &self.full_dual),
+        );
+        let old_dangling = self.dangling_boundaries.iter().collect::<HashSet<_>>();
+        let new_dangling = dangling_boundaries.iter().collect::<HashSet<_>>();
+        for &n in new_dangling.difference(&old_dangling) {
+            self.dual.remove_node(*n);
+        }
+        let restored = old_dangling
+            .difference(&new_dangling)
+            .copied()
+            .collect_vec();
+        for &n in &restored {
+            self.dual.add_node(*n);
+        }
+        for &n in &restored {
+            for (_, m, &used) in self.full_dual.edges(*n) {
+                if self.dual.contains_node(m) {
+                    self.dual.add_edge(*n, m, used);
+                }
+            }
+        }
+
+        let new_boundary = dual_boundaries
+            .iter()
+            .chain(&dangling_boundaries)
+            .copied()
+            .collect::<HashSet<_>>();
+        let dual_nodes = old_boundary
+            .symmetric_difference(&new_boundary)
+            .copied()
+            .chain(
+                new_dangling
+                    .symmetric_difference(&old_dangling)
+                    .map(|&&n| n),
+            )
+            .unique()
+            .sorted()
+            .collect();
+        self.dual_boundaries = dual_boundaries;
+        self.dangling_boundaries = dangling_boundaries;
+        dual_nodes
+    }
+}
+
+/// Remove the qubits or the coupler of the defect from the dead ones of the
+/// config.
+fn revive(
+    primal: &CutGraph,
+    config: &mut TopologyConfig,
+    qubits_map: &IndexMap<Point, u32>,
+    defect: &Defect,
+) -> Result<(), GraphError> {
+    let unknown = || GraphError::UnknownDefects {
+        defects: vec![defect.clone()],
+    };
+    config.defects.retain(|d| d != defect);
+    if let &Defect::Coupler(p1, p2) = defect {
+        if !primal.contains_edge(p1, p2) {
+            return Err(unknown());
+        }
+        let (i1, i2) = (qubits_map[&p1], qubits_map[&p2]);
+        config
+            .unused_couplers
+            .retain(|&c| c != (i1, i2) && c != (i2, i1));
+        config.defects.retain(|d| *d != Defect::Coupler(p2, p1));
+        return Ok(());
+    }
+    let qubits = qubits_map
+        .iter()
+        .filter(|&(&q, _)| defect.contains(q))
+        .collect_vec();
+    if qubits.is_empty() {
+        return Err(unknown());
+    }
+    for (&q, &i) in qubits {
+        config.unused_qubits.retain(|&u| u != i);
+        config.defects.retain(|d| *d != Defect::Qubit(q));
+        if let Some(d) = config.defects.iter().find(|d| d.contains(q)) {
+            return Err(GraphError::CoveredByDefect {
+                qubit: q,
+                defect: d.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AlgorithmConfigBuilder, TopologyConfigBuilder};
+    use crate::cutline::search_cutlines;
+    use crate::graph::Lattice;
+
+    /// The edited graph matches the graph built from its config.
+    fn assert_rebuilt(graph: &SearchGraph) {
+        let rebuilt = SearchGraph::from_config(graph.config.clone()).unwrap();
+        let edges = |g: &CutGraph| {
+            g.all_edges()
+                .map(|(n1, n2, &used)| (n1.min(n2), n1.max(n2), used))
+                .sorted()
+                .collect_vec()
+        };
+        assert_eq!(edges(&graph.primal), edges(&rebuilt.primal));
+        assert_eq!(edges(&graph.dual), edges(&rebuilt.dual));
+        assert_eq!(edges(&graph.full_dual), edges(&rebuilt.full_dual));
+        assert_eq!(
+            graph.dual.nodes().sorted().collect_vec(),
+            rebuilt.dual.nodes().sorted().collect_vec()
+        );
+        assert_eq!(graph.unused_qubits, rebuilt.unused_qubits);
+        assert_eq!(graph.dual_boundaries, rebuilt.dual_boundaries);
+        assert_eq!(graph.dangling_boundaries, rebuilt.dangling_boundaries);
+    }
+
+    /// The valid cutlines are found again by a new search.
+    fn assert_valid_cutlines(
+        graph: &SearchGraph,
+        update: &GraphUpdate,
+        cutlines: &[Cutline],
+        algorithm: &AlgorithmConfig,
+    ) -> usize {
+        let valid = update.valid_cutlines(graph, cutlines, algorithm);
+        let searched = search_cutlines(graph, algorithm)
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.canonical_key(), c.unbalance))
+            .collect::<HashSet<_>>();
+        for (i, cutline) in &valid {
+            assert_eq!(cutline.split, cutlines[*i].split);
+            assert!(searched.contains(&(cutline.canonical_key(), cutline.unbalance)));
+        }
+        valid.len()
+    }
+
+    #[test]
+    fn test_edit_rotated() {
+        let config = TopologyConfigBuilder::default()
+            .width(6)
+            .height(6)
+            .build()
+            .unwrap();
+        let algorithm = AlgorithmConfigBuilder::default()
+            .max_depth(6)
+            .build()
+            .unwrap();
+        let mut graph = SearchGraph::from_config(config.clone()).unwrap();
+        let cutlines = search_cutlines(&graph, &algorithm).unwrap();
+
+        // a dead corner qubit leaves dangling routers
+        let update = graph.kill(Defect::Qubit((1, 0))).unwrap();
+        assert_eq!(update.qubits, vec![(1, 0)]);
+        assert_eq!(update.couplers.len(), 2);
+        assert!(!graph.dangling_boundaries.is_empty());
+        assert_rebuilt(&graph);
+        let n_valid = assert_valid_cutlines(&graph, &update, &cutlines, &algorithm);
+        assert!(n_valid > 0 && n_valid < cutlines.len());
+
+        // a dead inner coupler does not touch the boundary
+        let cutlines = search_cutlines(&graph, &algorithm).unwrap();
+        let update = graph.kill(Defect::Coupler((2, 3), (3, 2))).unwrap();
+        assert!(update.dual_nodes.is_empty());
+        assert_rebuilt(&graph);
+        assert_valid_cutlines(&graph, &update, &cutlines, &algorithm);
+
+        // reviving everything restores the original graph
+        let cutlines = search_cutlines(&graph, &algorithm).unwrap();
+        let update = graph
+            .apply_edits(&[
+                Edit::Revive(Defect::Qubit((1, 0))),
+                Edit::Revive(Defect::Coupler((3, 2), (2, 3))),
+            ])
+            .unwrap();
+        assert!(graph.dangling_boundaries.is_empty());
+        assert_eq!(graph.config, config);
+        assert_rebuilt(&graph);
+        assert_valid_cutlines(&graph, &update, &cutlines, &algorithm);
+        assert!(graph.revive(Defect::Qubit((1, 0))).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_edit_errors() {
+        let config = TopologyConfigBuilder::default()
+            .width(6)
+            .height(6)
+            .defects(vec!["rect:0,0:1,1".parse().unwrap()])
+            .build()
+            .unwrap();
+        let mut graph = SearchGraph::from_config(config.clone()).unwrap();
+        assert!(matches!(
+            graph.revive(Defect::Qubit((0, 1))),
+            Err(GraphError::CoveredByDefect { qubit: (0, 1), .. })
+        ));
+        assert!(matches!(
+            graph.kill(Defect::Qubit((0, 0))),
+            Err(GraphError::UnknownDefects { .. })
+        ));
+        // cut the corner qubit (5, 0) off
+        assert!(matches!(
+            graph.kill(Defect::Qubit((4, 1))),
+            Err(GraphError::Disconnected { .. })
+        ));
+        assert_eq!(graph.config, config);
+        assert_rebuilt(&graph);
+
+        graph.revive("rect:0,0:1,1".parse().unwrap()).unwrap();
+        assert!(graph.unused_qubits.is_empty());
+        assert_rebuilt(&graph);
+    }
+
+    #[test]
+    fn test_edit_layout() {
+        let config = TopologyConfigBuilder::default()
+            .width(11)
+            .height(3)
+            .lattice(Lattice::HeavyHex)
+            .build()
+            .unwrap();
+        let algorithm = AlgorithmConfigBuilder::default()
+            .max_depth(5)
+            .endpoints(Some("top:bottom".parse().unwrap()))
+            .build()
+            .unwrap();
+        let mut graph = SearchGraph::from_config(config).unwrap();
+        let cutlines = search_cutlines(&graph, &algorithm).unwrap();
+        // the faces around an inner qubit merge away from the boundary
+        let update = graph.kill(Defect::Qubit((4, 1))).unwrap();
+        assert!(update.dual_nodes.is_empty());
+        assert_rebuilt(&graph);
+        let n_valid = assert_valid_cutlines(&graph, &update, &cutlines, &algorithm);
+        assert!(n_valid > 0);
+
+        // the face behind a dead boundary coupler joins the boundary
+        let cutlines = search_cutlines(&graph, &algorithm).unwrap();
+        let update = graph.kill(Defect::Coupler((0, 0), (1, 0))).unwrap();
+        assert!(!update.dual_nodes.is_empty());
+        assert_rebuilt(&graph);
+        assert_valid_cutlines(&graph, &update, &cutlines, &algorithm);
+    }
+
+    #[test]
+    fn test_edit_links() {
+        let config = TopologyConfigBuilder::default()
+            .width(12)
+            .height(6)
+            .tiles(vec!["6x6@0,0".parse().unwrap(), "6x6@6,0".parse().unwrap()])
+            .links(vec![(2, 33), (14, 21), (26, 9)])
+            .build()
+            .unwrap();
+        let algorithm = AlgorithmConfigBuilder::default()
+            .max_depth(6)
+            .max_unbalance(6)
+            .build()
+            .unwrap();
+        let mut graph = SearchGraph::from_config(config).unwrap();
+        let cutlines = search_cutlines(&graph, &algorithm).unwrap();
+        // the cutlines crossing the dead link are gone
+        let update = graph.kill(Defect::Coupler((5, 2), (6, 3))).unwrap();
+        assert_eq!(update.couplers, vec![((5, 2), (6, 3))]);
+        assert_rebuilt(&graph);
+        let n_valid = assert_valid_cutlines(&graph, &update, &cutlines, &algorithm);
+        assert!(n_valid > 0 && n_valid < cutlines.len());
+
+        // a dead qubit on the border of a tile kills a link
+        let cutlines = search_cutlines(&graph, &algorithm).unwrap();
+        let update = graph.kill(Defect::Qubit((6, 1))).unwrap();
+        assert_eq!(update.couplers.len(), 3);
+        assert_rebuilt(&graph);
+        assert_valid_cutlines(&graph, &update, &cutlines, &algorithm);
+    }
+}
@@ -0,0 +1,369 @@
+//! Export of the primal graph, the dual graph and the dual boundary of a
+//! [`SearchGraph`] to GraphViz DOT and JSON, to inspect the cut enumeration.
+//!
+//! Both exports flag the unused qubits and couplers, the long-range links
+//! between tiles, the dual edges crossing an unused coupler, the virtual
+//! halves of the split parallel dual edges, the boundary routers and the
+//! dangling boundary routers removed from the dual graph together with their
+//! dual edges.
+//!
+//! In the DOT output the qubits are named `q(x, y)` and the dual nodes
+//! `d(x, y)`. The qubits are pinned at their coordinates, and so are the dual
+//! nodes of a rotated grid, use `neato -n` to keep them in place:
+//!
+//! - unused qubits and couplers are gray and dashed, links are bold,
+//! - dual edges are orange, dashed if they cross an unused coupler and
+//!   dotted if they are virtual,
+//! - boundary routers are blue squares labeled with their clockwise
+//!   position, see [`clockwise_boundary`],
+//! - the removed dangling routers and their dual edges are red and dashed.
+use crate::boundary::{clockwise_boundary, BoundaryRouter};
+use crate::cutline::Edge;
+use crate::graph::{Lattice, Point, SearchGraph};
+use anyhow::Result;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::fs::File;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QubitExport {
+    /// Row-major index of the qubit, as used in the topology config
+    pub index: u32,
+    pub position: Point,
+    pub used: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CouplerExport {
+    pub qubits: Edge,
+    /// Index of the coupler in the edge table of the graph
+    pub edge_index: usize,
+    pub used: bool,
+    /// Whether the coupler is a long-range link between tiles, which crosses
+    /// no dual edge
+    #[serde(default)]
+    pub is_link: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DualNodeExport {
+    pub node: Point,
+    pub boundary: bool,
+    /// Whether the node is a dangling boundary router removed from the dual
+    pub removed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DualEdgeExport {
+    pub nodes: Edge,
+    /// The coupler crossed by the dual edge, `None` for a virtual half
+    pub crosses: Option<Edge>,
+    /// Whether the crossed coupler is used
+    pub used: bool,
+    /// Whether the edge was removed with a dangling boundary router
+    pub removed: bool,
+}
+
+/// The primal graph, dual graph and dual boundary of a [`SearchGraph`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphExport {
+    pub qubits: Vec<QubitExport>,
+    pub couplers: Vec<CouplerExport>,
+    pub dual_nodes: Vec<DualNodeExport>,
+    pub dual_edges: Vec<DualEdgeExport>,
+    /// The boundary routers in the order the cutlines are searched from
+    pub dual_boundaries: Vec<Point>,
+    /// The boundary routers in clockwise order with their side, the
+    /// positions of the boundary arcs of the cutline endpoints
+    pub clockwise_boundary: Vec<BoundaryRouter>,
+    /// Whether the dual nodes are grid points, which is only the case for
+    /// the rotated grid
+    pub dual_positions: bool,
+}
+
+impl GraphExport {
+    pub fn new(graph: &SearchGraph) -> Self {
+        let primal = &graph.primal;
+        // the qubits are added to the primal graph in row-major order
+        let qubits = primal
+            .nodes()
+            .enumerate()
+            .map(|(i, position)| QubitExport {
+                index: i as u32,
+                position,
+                used: !graph.unused_qubits.contains(&position),
+            })
+            .collect();
+        let couplers = graph
+            .edges
+            .iter()
+            .enumerate()
+            .map(|(edge_index, &(n1, n2))| CouplerExport {
+                qubits: (n1, n2),
+                edge_index,
+                used: *primal.edge_weight(n1, n2).unwrap(),
+                is_link: graph.is_link(n1, n2),
+            })
+            .collect();
+        let removed = &graph.dangling_boundaries;
+        let dual_nodes = graph
+            .dual
+            .nodes()
+            .map(|node| DualNodeExport {
+                node,
+                boundary: graph.dual_boundaries.contains(&node),
+                removed: false,
+            })
+            .chain(removed.iter().map(|&node| DualNodeExport {
+                node,
+                boundary: true,
+                removed: true,
+            }))
+            .collect();
+        let crossed_used = |crosses: Option<Edge>| {
+            crosses.is_some_and(|(n1, n2)| *primal.edge_weight(n1, n2).unwrap())
+        };
+        let dual_edges = graph
+            .dual
+            .all_edges()
+            .map(|(d1, d2, _)| {
+                let crosses = graph.crossed_edge(d1, d2);
+                DualEdgeExport {
+                    nodes: (d1.min(d2), d1.max(d2)),
+                    crosses,
+                    used: crossed_used(crosses),
+                    removed: false,
+                }
+            })
+            .chain(
+                // the removed edges include the virtual halves, which cross nothing
+                graph
+                    .full_dual
+                    .all_edges()
+                    .filter(|&(d1, d2, _)| !graph.dual.contains_edge(d1, d2))
+                    .map(|(d1, d2, _)| (d1.min(d2), d1.max(d2)))
+                    .sorted()
+                    .map(|nodes| {
+                        let crosses = graph.crossed_edge(nodes.0, nodes.1);
+                        DualEdgeExport {
+                            nodes,
+                            crosses,
+                            used: crossed_used(crosses),
+                            removed: true,
+                        }
+                    }),
+            )
+            .collect();
+        Self {
+            qubits,
+            couplers,
+            dual_nodes,
+            dual_edges,
+            dual_boundaries: graph.dual_boundaries.clone(),
+            clockwise_boundary: clockwise_boundary(graph),
+            dual_positions: graph.config.layout.is_none()
+                && graph.config.lattice == Lattice::Rotated,
+        }
+    }
+
+    pub fn save_to_json(&self, path: &Path) -> Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    /// The GraphViz DOT form, see the [module documentation](self).
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        let pos = |p: Point| format!("pos=\"{},{}!\"", p.0, -p.1);
+        let qubit = |p: Point| format!("\"q({}, {})\"", p.0, p.1);
+        let dual = |p: Point| format!("\"d({}, {})\"", p.0, p.1);
+        writeln!(dot, "graph search_graph {{").unwrap();
+        writeln!(
+            dot,
+            "  node [fontsize=8, width=0.3, height=0.3, fixedsize=true];"
+        )
+        .unwrap();
+        for q in &self.qubits {
+            let style = if q.used {
+                ""
+            } else {
+                ", color=gray, fontcolor=gray, style=dashed"
+            };
+            writeln!(
+                dot,
+                "  {} [label=\"{}\", shape=circle, {}{}];",
+                qubit(q.position),
+                q.index,
+                pos(q.position),
+                style
+            )
+            .unwrap();
+        }
+        for c in &self.couplers {
+            let style = match (c.used, c.is_link) {
+                (true, false) => "",
+                (true, true) => " [style=bold]",
+                (false, false) => " [color=gray, style=dashed]",
+                (false, true) => " [color=gray, style=\"dashed,bold\"]",
+            };
+            writeln!(
+                dot,
+                "  {} -- {}{};",
+                qubit(c.qubits.0),
+                qubit(c.qubits.1),
+                style
+            )
+            .unwrap();
+        }
+        for d in &self.dual_nodes {
+            let style = match (d.removed, d.boundary) {
+                (true, _) => "shape=square, color=red, style=dashed",
+                (false, true) => "shape=square, color=blue",
+                (false, false) => "shape=point, color=orange",
+            };
+            let position = if self.dual_positions {
+                format!(", {}", pos(d.node))
+            } else {
+                String::new()
+            };
+            let label = self
+                .clockwise_boundary
+                .iter()
+                .position(|r| r.node == d.node)
+                .map_or(String::new(), |i| i.to_string());
+            writeln!(
+                dot,
+                "  {} [label=\"{}\", {}{}];",
+                dual(d.node),
+                label,
+                style,
+                position
+            )
+            .unwrap();
+        }
+        for e in &self.dual_edges {
+            let style = match (e.removed, e.crosses, e.used) {
+                (true, ..) => "color=red, style=dashed",
+                (false, None, _) => "color=orange, style=dotted",
+                (false, Some(_), false) => "color=orange, style=dashed",
+                (false, Some(_), true) => "color=orange",
+            };
+            writeln!(
+                dot,
+                "  {} -- {} [{}];",
+                dual(e.nodes.0),
+                dual(e.nodes.1),
+                style
+            )
+            .unwrap();
+        }
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{TopologyConfig, TopologyConfigBuilder};
+
+    #[test]
+    fn test_export_dangling_boundary() {
+        let mut config = TopologyConfig::default();
+        config.unused_qubits.extend([5, 11]);
+        let graph = SearchGraph::from_config(config).unwrap();
+        let export = GraphExport::new(&graph);
+        assert_eq!(export.qubits.len(), 66);
+        assert_eq!(export.qubits.iter().filter(|q| !q.used).count(), 2);
+        assert_eq!(export.qubits[5].position, (11, 0));
+        assert_eq!(export.couplers.iter().filter(|c| !c.used).count(), 4);
+
+        let removed = export.dual_nodes.iter().filter(|d| d.removed).collect_vec();
+        assert_eq!(removed.len(), graph.dangling_boundaries.len());
+        assert_eq!(
+            export.dual_nodes.len(),
+            graph.dual.node_count() + removed.len()
+        );
+        assert!(export
+            .dual_edges
+            .iter()
+            .filter(|e| e.removed)
+            .all(|e| !e.used
+                && removed
+                    .iter()
+                    .any(|d| d.node == e.nodes.0 || d.node == e.nodes.1)));
+        assert_eq!(
+            export.dual_edges.iter().filter(|e| !e.removed).count(),
+            graph.dual.edge_count()
+        );
+        let json = serde_json::to_string(&export).unwrap();
+        assert_eq!(serde_json::from_str::<GraphExport>(&json).unwrap(), export);
+
+        let dot = export.to_dot();
+        assert!(dot.starts_with("graph search_graph {"));
+        assert!(dot.contains("\"q(11, 0)\" [label=\"5\", shape=circle, pos=\"11,0!\", color=gray"));
+        assert!(dot.contains("\"q(10, 1)\" -- \"q(11, 0)\" [color=gray, style=dashed];"));
+        assert!(dot.contains("color=red, style=dashed"));
+        assert!(dot.contains("\"d(0, 0)\" [label=\"0\", shape=square, color=blue"));
+    }
+
+    #[test]
+    fn test_export_virtual_halves() {
+        let config = TopologyConfigBuilder::default()
+            .width(11)
+            .height(5)
+            .lattice(Lattice::HeavyHex)
+            .build()
+            .unwrap();
+        let graph = SearchGraph::from_config(config).unwrap();
+        let export = GraphExport::new(&graph);
+        assert!(!export.dual_positions);
+        assert!(export.dual_edges.iter().any(|e| e.crosses.is_none()));
+        assert_eq!(
+            export.dual_nodes.iter().filter(|d| d.boundary).count(),
+            graph.dual_boundaries.len()
+        );
+        let dot = export.to_dot();
+        assert!(dot.contains("style=dotted"));
+        assert!(!dot.contains("\"d(0, 0)\" [label=\"\", shape=point, color=orange, pos"));
+
+        // the dangling router takes the virtual half of its split edge along
+        let config = TopologyConfigBuilder::default()
+            .width(11)
+            .height(5)
+            .lattice(Lattice::HeavyHex)
+            .unused_qubits(vec![22])
+            .build()
+            .unwrap();
+        let graph = SearchGraph::from_config(config).unwrap();
+        let export = GraphExport::new(&graph);
+        let removed = export.dual_edges.iter().filter(|e| e.removed).collect_vec();
+        assert_eq!(
+            removed.len(),
+            graph.full_dual.edge_count() - graph.dual.edge_count()
+        );
+        assert!(removed.iter().any(|e| e.crosses.is_none()));
+    }
+
+    #[test]
+    fn test_export_links() {
+        let config = TopologyConfigBuilder::default()
+            .width(12)
+            .height(6)
+            .tiles(vec!["6x6@0,0".parse().unwrap(), "6x6@6,0".parse().unwrap()])
+            .links(vec![(2, 33), (14, 21), (26, 9)])
+            .build()
+            .unwrap();
+        let graph = SearchGraph::from_config(config).unwrap();
+        let export = GraphExport::new(&graph);
+        let links = export.couplers.iter().filter(|c| c.is_link).collect_vec();
+        assert_eq!(links.len(), 3);
+        assert_eq!(links[0].qubits, ((5, 0), (6, 5)));
+        assert!(export
+            .to_dot()
+            .contains("\"q(5, 0)\" -- \"q(6, 5)\" [style=bold];"));
+    }
+}
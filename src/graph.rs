@@ -1,85 +1,357 @@
 use crate::config::TopologyConfig;
-use anyhow::{bail, Ok, Result};
-use indexmap::IndexMap;
+use crate::cutline::Edge;
+use crate::defect::Defect;
+use crate::input::InputError;
+use crate::layout::Layout;
+use crate::modular::{resolve_links, tile_qubits, Tile};
+use clap::ValueEnum;
+use indexmap::{IndexMap, IndexSet};
 use itertools::Itertools;
-use petgraph::{algo::connected_components, graphmap::UnGraphMap};
+use petgraph::{graphmap::UnGraphMap, visit::Dfs};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 
 pub type CutGraph = UnGraphMap<(i32, i32), bool>;
 pub type Point = (i32, i32);
 
+/// The lattices which can be generated from the width and height of the
+/// topology config, see [`heavy_hex_layout`] and [`square_layout`] for the
+/// lattices other than the rotated grid.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Lattice {
+    /// Square lattice rotated by 45 degrees, each qubit has up to 4 diagonal
+    /// couplers
+    #[default]
+    Rotated,
+    /// Heavy-hex lattice of `height` chains of `width` qubits
+    HeavyHex,
+    /// Axis-aligned square lattice with horizontal and vertical couplers
+    Square,
+}
+
+/// A connected component of the used qubits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Component {
+    /// Row-major indices of the qubits, as used in the topology config
+    pub qubits: Vec<u32>,
+    /// Coordinates of the qubits, in the same order as `qubits`
+    pub coordinates: Vec<Point>,
+}
+
+/// Errors in the topology config found when constructing the [`SearchGraph`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphError {
+    /// The grid is too small to hold any coupler
+    DegenerateGrid { width: u32, height: u32 },
+    /// Unused qubit indices out of the range of the grid
+    QubitsOutOfRange { qubits: Vec<u32>, n_qubits: usize },
+    /// Unused couplers whose qubits are not neighbours on the grid
+    InvalidCouplers { couplers: Vec<(u32, u32)> },
+    /// Defects whose coordinates match no qubit or coupler of the graph
+    UnknownDefects { defects: Vec<Defect> },
+    /// A revived qubit stays dead under a region defect of the config
+    CoveredByDefect { qubit: Point, defect: Defect },
+    /// The used qubits do not form a single connected component, the
+    /// components are sorted by their size in descending order
+    Disconnected { components: Vec<Component> },
+    /// The layout file of the config cannot be loaded
+    Input(InputError),
+    /// The layout is not a valid coupling graph
+    InvalidLayout { message: String },
+    /// Pairs of couplers crossing each other in the layout
+    CrossingCouplers {
+        crossings: Vec<((u32, u32), (u32, u32))>,
+    },
+    /// The device does not fit on the rotated grid
+    InvalidDevice { message: String },
+    /// The tiles of a modular topology do not fit on the grid
+    InvalidTiles { message: String },
+    /// Links whose qubits are out of range or in the same tile
+    InvalidLinks { links: Vec<(u32, u32)> },
+    /// The chip mask does not draw a rotated grid
+    InvalidMask { message: String },
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::DegenerateGrid { width, height } => write!(
+                f,
+                "The grid of width {} and height {} is degenerate, both should be at least 2",
+                width, height
+            ),
+            GraphError::QubitsOutOfRange { qubits, n_qubits } => write!(
+                f,
+                "Unused qubits {:?} are out of range, the grid only has {} qubits",
+                qubits, n_qubits
+            ),
+            GraphError::InvalidCouplers { couplers } => write!(
+                f,
+                "Unused couplers {:?} do not connect neighbouring qubits",
+                couplers
+            ),
+            GraphError::UnknownDefects { defects } => write!(
+                f,
+                "Defects [{}] do not match any qubit or coupler",
+                defects.iter().join(", ")
+            ),
+            GraphError::CoveredByDefect { qubit, defect } => write!(
+                f,
+                "Qubit {:?} cannot be revived, it lies in the defect {}",
+                qubit, defect
+            ),
+            GraphError::Disconnected { components } if components.is_empty() => {
+                write!(f, "The graph has no used qubit")
+            }
+            GraphError::Disconnected { components } => {
+                write!(
+                    f,
+                    "The graph is not single connected, found {} connected components:",
+                    components.len()
+                )?;
+                for (i, component) in components.iter().enumerate() {
+                    write!(
+                        f,
+                        "\n  component {} ({} qubits):",
+                        i,
+                        component.qubits.len()
+                    )?;
+                    for (q, p) in component.qubits.iter().zip(&component.coordinates) {
+                        write!(f, " {}:{:?}", q, p)?;
+                    }
+                }
+                Ok(())
+            }
+            GraphError::Input(e) => write!(f, "{}", e),
+            GraphError::InvalidLayout { message } => write!(f, "Invalid layout: {}", message),
+            GraphError::CrossingCouplers { crossings } => {
+                write!(f, "The layout is not planar, crossing couplers:")?;
+                for (c1, c2) in crossings {
+                    write!(f, "\n  {:?} crosses {:?}", c1, c2)?;
+                }
+                Ok(())
+            }
+            GraphError::InvalidDevice { message } => write!(f, "Invalid device: {}", message),
+            GraphError::InvalidTiles { message } => write!(f, "Invalid tiles: {}", message),
+            GraphError::InvalidLinks { links } => write!(
+                f,
+                "Links {:?} do not join the qubits of two different tiles",
+                links
+            ),
+            GraphError::InvalidMask { message } => write!(f, "Invalid mask: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+impl From<InputError> for GraphError {
+    fn from(e: InputError) -> Self {
+        GraphError::Input(e)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SearchGraph {
     pub config: TopologyConfig,
     pub primal: CutGraph,
+    /// On a rotated grid the dual nodes are the grid points without qubit.
+    /// On a loaded layout they are numbered as `(i, 0)` for the faces,
+    /// `(i, 1)` for the boundary routers and `(i, 2)` for the midpoints
+    /// splitting parallel dual edges.
     pub dual: CutGraph,
     pub unused_qubits: Vec<Point>,
+    /// The islands cut off from the largest connected component and marked
+    /// unused, only set with `drop_islands`
+    pub dropped_islands: Vec<Component>,
     pub dual_boundaries: Vec<Point>,
+    /// The boundary routers whose dual edges all cross unused couplers,
+    /// removed from the dual graph and its boundary
+    pub dangling_boundaries: Vec<Point>,
+    /// The dual nodes the boundary is contracted or spread from, the grid
+    /// points on the border or the routers of a layout
+    pub(crate) boundary_seeds: Vec<Point>,
+    /// The dual graph with the dangling boundary routers, kept to restore
+    /// them when a topology edit revives their couplers
+    pub(crate) full_dual: CutGraph,
+    /// The long-range couplers between the tiles of a modular topology,
+    /// which cross no dual edge
+    pub links: Vec<Edge>,
+    /// All the couplers of the primal graph, the position of a coupler in
+    /// the table is its edge index
+    pub edges: IndexSet<Edge>,
+    /// The coupler crossed by each dual edge, keyed by the sorted dual edge.
+    /// The virtual second half of a split parallel dual edge crosses nothing.
+    pub crossings: HashMap<Edge, Edge>,
 }
 
 impl SearchGraph {
-    pub fn from_config(config: TopologyConfig) -> Result<Self> {
-        let (primal, unused_qubits) = create_primal(&config)?;
-        let mut dual = create_dual(&primal);
-        let width = config.width;
-        let height = config.height;
-        let mut dual_boundaries = get_dual_boundary(&dual, width, height);
-        let dangling_nodes = dangling_nodes(&dual);
-        dangling_nodes
-            .iter()
-            .filter(|&n| dual_boundaries.contains(n))
-            .for_each(|n| {
-                dual.remove_node(*n);
+    /// Build the graph of the lattice of the config, or of the layout file
+    /// referenced by the config.
+    pub fn from_config(config: TopologyConfig) -> Result<Self, GraphError> {
+        if !config.tiles.is_empty()
+            && (config.layout.is_some() || config.lattice != Lattice::Rotated)
+        {
+            return Err(GraphError::InvalidTiles {
+                message: "tiles are only cut from the rotated grid".to_string(),
             });
-        dual_boundaries.retain(|n| !dangling_nodes.contains(n));
-        Ok(Self {
+        }
+        if let Some(path) = &config.layout {
+            let layout = Layout::from_file(path)?;
+            return Self::from_layout(config, &layout);
+        }
+        let layout = match config.lattice {
+            Lattice::Rotated => None,
+            Lattice::HeavyHex => Some(heavy_hex_layout(config.width, config.height)?),
+            Lattice::Square => Some(square_layout(config.width, config.height)?),
+        };
+        if let Some(layout) = layout {
+            return Self::from_layout(config, &layout);
+        }
+        let (primal, unused_qubits, links) = create_primal(&config)?;
+        let (dual, crossings) = create_dual(&primal, &links);
+        let boundary_seeds = if config.tiles.is_empty() {
+            grid_boundary_seeds(&dual, config.width, config.height)
+        } else {
+            tile_boundary_seeds(&dual, &config.tiles)
+        };
+        Ok(Self::new(
             config,
             primal,
             unused_qubits,
+            links,
+            dual,
+            boundary_seeds,
+            crossings,
+        ))
+    }
+
+    /// Build the graph of a planar layout, the dual graph and its boundary
+    /// are derived from the faces of the embedding. The width, height and
+    /// origin of the config are ignored.
+    pub fn from_layout(config: TopologyConfig, layout: &Layout) -> Result<Self, GraphError> {
+        layout.validate()?;
+        let qubits_map: IndexMap<_, _> = layout
+            .qubits
+            .iter()
+            .enumerate()
+            .map(|(i, &p)| (p, i as u32))
+            .collect();
+        let mut primal = UnGraphMap::new();
+        for &p in &layout.qubits {
+            primal.add_node(p);
+        }
+        for &(i1, i2) in &layout.couplers {
+            primal.add_edge(layout.qubits[i1 as usize], layout.qubits[i2 as usize], true);
+        }
+        let unused_qubits = mark_unused(&mut primal, &qubits_map, &config)?;
+        let (dual, crossings, routers) = create_planar_dual(&primal)?;
+        Ok(Self::new(
+            config,
+            primal,
+            unused_qubits,
+            Vec::new(),
+            dual,
+            routers,
+            crossings,
+        ))
+    }
+
+    fn new(
+        config: TopologyConfig,
+        primal: CutGraph,
+        (unused_qubits, dropped_islands): UnusedQubits,
+        links: Vec<Edge>,
+        full_dual: CutGraph,
+        boundary_seeds: Vec<Point>,
+        crossings: HashMap<Edge, Edge>,
+    ) -> Self {
+        let (dual_boundaries, dangling_boundaries) =
+            split_dangling(&full_dual, contract_boundary(&boundary_seeds, &full_dual));
+        let mut dual = full_dual.clone();
+        dangling_boundaries.iter().for_each(|n| {
+            dual.remove_node(*n);
+        });
+        let edges = edge_table(&primal);
+        Self {
+            config,
+            primal,
+            unused_qubits,
+            dropped_islands,
             dual,
             dual_boundaries,
-        })
+            dangling_boundaries,
+            boundary_seeds,
+            full_dual,
+            links,
+            edges,
+            crossings,
+        }
     }
 
     pub fn num_slash(&self) -> usize {
-        let primal = &self.primal;
         let primal_width = self.config.width as i32;
-        primal
-            .nodes()
-            .filter(|&n| {
-                (n.1 == 0 || n.0 == primal_width - 1)
-                    && primal.edge_weight(n, (n.0 - 1, n.1 + 1)).is_some()
-            })
-            .count()
+        self.num_diagonals(|n| n.1 == 0 || n.0 == primal_width - 1, (-1, 1))
     }
 
     pub fn num_back_slash(&self) -> usize {
-        let primal = &self.primal;
         let primal_width = self.config.width as i32;
         let primal_height = self.config.height as i32;
-        primal
-            .nodes()
-            .filter(|&n| {
-                (n.1 == primal_height - 1 || n.0 == primal_width - 1)
-                    && primal.edge_weight(n, (n.0 - 1, n.1 - 1)).is_some()
-            })
-            .count()
+        self.num_diagonals(
+            |n| n.1 == primal_height - 1 || n.0 == primal_width - 1,
+            (-1, -1),
+        )
     }
 
+    /// Count the qubits on the border starting a diagonal of couplers in the
+    /// direction. The tiles of a modular topology keep the diagonals of the
+    /// whole grid, so that they share its patterns.
+    fn num_diagonals(&self, on_border: impl Fn(Point) -> bool, (dx, dy): Point) -> usize {
+        let primal = &self.primal;
+        if self.config.tiles.is_empty() {
+            return primal
+                .nodes()
+                .filter(|&n| on_border(n) && primal.edge_weight(n, (n.0 + dx, n.1 + dy)).is_some())
+                .count();
+        }
+        let (width, height) = (self.config.width as i32, self.config.height as i32);
+        grid_qubits(
+            self.config.width,
+            self.config.height,
+            self.config.qubit_at_origin,
+        )
+        .into_keys()
+        .filter(|&n| {
+            on_border(n) && (0..width).contains(&(n.0 + dx)) && (0..height).contains(&(n.1 + dy))
+        })
+        .count()
+    }
+
+    /// Whether the qubits are joined by a link between two tiles.
+    #[inline]
+    pub fn is_link(&self, n1: Point, n2: Point) -> bool {
+        self.links.contains(&(n1.min(n2), n1.max(n2)))
+    }
+
+    /// Index of the coupler in the edge table, panics if the qubits are not
+    /// coupled.
     #[inline(always)]
     pub fn edge_index(&self, n1: Point, n2: Point) -> usize {
-        ((n1.1 + n2.1) / 2) as usize * (self.config.width - 1) as usize
-            + ((n1.0 + n2.0) / 2) as usize
+        self.edges.get_index_of(&(n1.min(n2), n1.max(n2))).unwrap()
     }
 
     #[inline(always)]
-    pub fn get_edge(&self, index: usize) -> (Point, Point) {
-        let width = self.config.width as usize - 1;
-        let (quotient, remainder) = ((index / width) as i32, (index % width) as i32);
-        if self.primal.contains_node((remainder, quotient)) {
-            ((remainder, quotient), (remainder + 1, quotient + 1))
-        } else {
-            ((remainder, quotient + 1), (remainder + 1, quotient))
-        }
+    pub fn get_edge(&self, index: usize) -> Edge {
+        self.edges[index]
+    }
+
+    /// The coupler crossed by the dual edge, `None` for the virtual halves of
+    /// split parallel dual edges.
+    #[inline]
+    pub fn crossed_edge(&self, r1: Point, r2: Point) -> Option<Edge> {
+        self.crossings.get(&(r1.min(r2), r1.max(r2))).copied()
     }
 }
 
@@ -97,18 +369,23 @@ pub fn duality_map(p1: Point, p2: Point) -> (Point, Point) {
     (dual_p1, dual_p2)
 }
 
-fn create_primal(config: &TopologyConfig) -> Result<(CutGraph, Vec<Point>)> {
+/// The primal graph of the rotated grid, or of its tiles joined by their
+/// links, with the unused qubits and the links.
+fn create_primal(
+    config: &TopologyConfig,
+) -> Result<(CutGraph, UnusedQubits, Vec<Edge>), GraphError> {
     let width = config.width;
     let height = config.height;
-    let unused_qubits = &config.unused_qubits;
-    let unused_couplers = &config.unused_couplers;
+    if width < 2 || height < 2 {
+        return Err(GraphError::DegenerateGrid { width, height });
+    }
     let mut primal = UnGraphMap::new();
-    let qubits_map: IndexMap<_, _> = (0..height)
-        .cartesian_product(0..width)
-        .filter(|&(y, x)| in_primal(x as i32, y as i32, config.qubit_at_origin))
-        .enumerate()
-        .map(|(i, (y, x))| ((x as i32, y as i32), i as u32))
-        .collect();
+    let mut qubits_map = grid_qubits(width, height, config.qubit_at_origin);
+    let tiles = &config.tiles;
+    if !tiles.is_empty() {
+        qubits_map = tile_qubits(tiles, qubits_map, (width, height))?;
+    }
+    let same_tile = |p1: Point, p2: Point| tiles.iter().all(|t| t.contains(p1) == t.contains(p2));
 
     qubits_map.iter().for_each(|(&n, _)| {
         primal.add_node(n);
@@ -118,13 +395,139 @@ fn create_primal(config: &TopologyConfig) -> Result<(CutGraph, Vec<Point>)> {
         if y == (height - 1) as i32 {
             return;
         }
-        if x > 0 {
+        if x > 0 && same_tile((x, y), (x - 1, y + 1)) {
             primal.add_edge((x, y), (x - 1, y + 1), true);
         }
-        if x < (width - 1) as i32 {
+        if x < (width - 1) as i32 && same_tile((x, y), (x + 1, y + 1)) {
             primal.add_edge((x, y), (x + 1, y + 1), true);
         }
     });
+    let links = resolve_links(tiles, &qubits_map, &config.links)?;
+    for &(n1, n2) in &links {
+        primal.add_edge(n1, n2, true);
+    }
+    let unused_qubits = mark_unused(&mut primal, &qubits_map, config)?;
+    Ok((primal, unused_qubits, links))
+}
+
+/// The heavy-hex lattice of `height` chains of `width` qubits.
+///
+/// The chain `r` lies on the row `y = 2r`. The bridge qubits between the
+/// chains `r` and `r + 1` lie on the row `2r + 1` and connect the two chain
+/// qubits above and below them, every 4 columns starting from column 0 for
+/// even `r` and from column 2 for odd `r`. Each face is a hexagon with a
+/// qubit on every edge. The qubits are indexed in row-major order.
+pub fn heavy_hex_layout(width: u32, height: u32) -> Result<Layout, GraphError> {
+    if width < 2 || height < 2 {
+        return Err(GraphError::DegenerateGrid { width, height });
+    }
+    let (width, height) = (width as i32, height as i32);
+    let mut qubits = Vec::new();
+    for r in 0..height {
+        qubits.extend((0..width).map(|x| (x, 2 * r)));
+        if r + 1 < height {
+            let start = if r % 2 == 0 { 0 } else { 2 };
+            qubits.extend((start..width).step_by(4).map(|x| (x, 2 * r + 1)));
+        }
+    }
+    let index: HashMap<Point, u32> = qubits
+        .iter()
+        .enumerate()
+        .map(|(i, &p)| (p, i as u32))
+        .collect();
+    let mut couplers = Vec::new();
+    for &(x, y) in &qubits {
+        let i = index[&(x, y)];
+        if y % 2 == 0 && x + 1 < width {
+            couplers.push((i, index[&(x + 1, y)]));
+        }
+        if y % 2 == 1 {
+            couplers.push((index[&(x, y - 1)], i));
+            couplers.push((i, index[&(x, y + 1)]));
+        }
+    }
+    Ok(Layout { qubits, couplers })
+}
+
+/// The axis-aligned square lattice of `width` x `height` qubits, with the
+/// horizontal and vertical couplers between neighbouring qubits. The qubits
+/// are indexed in row-major order.
+pub fn square_layout(width: u32, height: u32) -> Result<Layout, GraphError> {
+    if width < 2 || height < 2 {
+        return Err(GraphError::DegenerateGrid { width, height });
+    }
+    let (width, height) = (width as i32, height as i32);
+    let qubits = (0..height)
+        .cartesian_product(0..width)
+        .map(|(y, x)| (x, y))
+        .collect_vec();
+    let index = |x: i32, y: i32| (y * width + x) as u32;
+    let mut couplers = Vec::new();
+    for &(x, y) in &qubits {
+        if x + 1 < width {
+            couplers.push((index(x, y), index(x + 1, y)));
+        }
+        if y + 1 < height {
+            couplers.push((index(x, y), index(x, y + 1)));
+        }
+    }
+    Ok(Layout { qubits, couplers })
+}
+
+/// The qubits of a rotated grid with their row-major indices.
+pub(crate) fn grid_qubits(width: u32, height: u32, qubit_at_origin: bool) -> IndexMap<Point, u32> {
+    (0..height)
+        .cartesian_product(0..width)
+        .filter(|&(y, x)| in_primal(x as i32, y as i32, qubit_at_origin))
+        .enumerate()
+        .map(|(i, (y, x))| ((x as i32, y as i32), i as u32))
+        .collect()
+}
+
+/// The unused qubits and the islands dropped from the graph.
+type UnusedQubits = (Vec<Point>, Vec<Component>);
+
+/// Validate the unused qubits and couplers of the config, mark the couplers
+/// touching them as unused and verify the used qubits are single connected.
+/// With `drop_islands` the qubits out of the largest component are marked
+/// unused instead.
+pub(crate) fn mark_unused(
+    primal: &mut CutGraph,
+    qubits_map: &IndexMap<Point, u32>,
+    config: &TopologyConfig,
+) -> Result<UnusedQubits, GraphError> {
+    let (defect_qubits, defect_couplers) = resolve_defects(primal, qubits_map, &config.defects)?;
+    let unused_qubits = &[config.unused_qubits.as_slice(), &defect_qubits].concat();
+    let unused_couplers = &[config.unused_couplers.as_slice(), &defect_couplers].concat();
+    let out_of_range = unused_qubits
+        .iter()
+        .filter(|&&i| i as usize >= qubits_map.len())
+        .copied()
+        .collect_vec();
+    if !out_of_range.is_empty() {
+        return Err(GraphError::QubitsOutOfRange {
+            qubits: out_of_range,
+            n_qubits: qubits_map.len(),
+        });
+    }
+    let invalid_couplers = unused_couplers
+        .iter()
+        .filter(|&&(i1, i2)| {
+            match (
+                qubits_map.get_index(i1 as usize),
+                qubits_map.get_index(i2 as usize),
+            ) {
+                (Some((&n1, _)), Some((&n2, _))) => !primal.contains_edge(n1, n2),
+                _ => true,
+            }
+        })
+        .copied()
+        .collect_vec();
+    if !invalid_couplers.is_empty() {
+        return Err(GraphError::InvalidCouplers {
+            couplers: invalid_couplers,
+        });
+    }
     // set unused couplers
     primal.all_edges_mut().for_each(|(n1, n2, edge)| {
         let i1 = qubits_map[&n1];
@@ -138,18 +541,80 @@ fn create_primal(config: &TopologyConfig) -> Result<(CutGraph, Vec<Point>)> {
         }
     });
 
-    let unused_qubits = qubits_map
+    let mut unused_qubits = qubits_map
         .iter()
         .filter(|&(_, i)| unused_qubits.contains(i))
         .map(|(&p, _)| p)
         .collect_vec();
 
     // Verify the graph is single connected
-    verify_single_connected(&primal, &unused_qubits)?;
-    Ok((primal, unused_qubits))
+    let components = used_components(primal, &unused_qubits)
+        .into_iter()
+        .map(|coordinates| Component {
+            qubits: coordinates.iter().map(|p| qubits_map[p]).collect(),
+            coordinates,
+        })
+        .collect_vec();
+    if components.is_empty() || (components.len() > 1 && !config.drop_islands) {
+        return Err(GraphError::Disconnected { components });
+    }
+    let dropped_islands = components[1..].to_vec();
+    for island in &dropped_islands {
+        for &p in &island.coordinates {
+            let neighbors = primal.neighbors(p).collect_vec();
+            for n in neighbors {
+                *primal.edge_weight_mut(p, n).unwrap() = false;
+            }
+        }
+        unused_qubits.extend(&island.coordinates);
+    }
+    // keep the unused qubits in the row-major order of the indices
+    unused_qubits.sort_by_key(|p| qubits_map[p]);
+    Ok((unused_qubits, dropped_islands))
+}
+
+/// The indices of the unused qubits and couplers.
+type UnusedIndices = (Vec<u32>, Vec<(u32, u32)>);
+
+/// Convert the defects given by coordinates into the indices of the unused
+/// qubits and couplers.
+fn resolve_defects(
+    primal: &CutGraph,
+    qubits_map: &IndexMap<Point, u32>,
+    defects: &[Defect],
+) -> Result<UnusedIndices, GraphError> {
+    let mut qubits = Vec::new();
+    let mut couplers = Vec::new();
+    let mut unknown = Vec::new();
+    for defect in defects {
+        match defect {
+            Defect::Coupler(p1, p2) if primal.contains_edge(*p1, *p2) => {
+                couplers.push((qubits_map[p1], qubits_map[p2]));
+            }
+            Defect::Coupler(..) => unknown.push(defect.clone()),
+            _ => {
+                let len = qubits.len();
+                qubits.extend(
+                    qubits_map
+                        .iter()
+                        .filter(|(&p, _)| defect.contains(p))
+                        .map(|(_, &i)| i),
+                );
+                if qubits.len() == len {
+                    unknown.push(defect.clone());
+                }
+            }
+        }
+    }
+    if !unknown.is_empty() {
+        return Err(GraphError::UnknownDefects { defects: unknown });
+    }
+    Ok((qubits, couplers))
 }
 
-fn verify_single_connected(graph: &CutGraph, unused_qubits: &Vec<Point>) -> Result<()> {
+/// Connected components of the used qubits through the used couplers,
+/// sorted by their size in descending order.
+fn used_components(graph: &CutGraph, unused_qubits: &[Point]) -> Vec<Vec<Point>> {
     let mut verify_graph = graph.clone();
     for unused_qubit in unused_qubits {
         verify_graph.remove_node(*unused_qubit);
@@ -159,24 +624,169 @@ fn verify_single_connected(graph: &CutGraph, unused_qubits: &Vec<Point>) -> Resu
             verify_graph.remove_edge(n1, n2);
         }
     }
-    if connected_components(&verify_graph) != 1 {
-        bail!("The graph is not single connected")
+    let mut visited = HashSet::new();
+    let mut components = Vec::new();
+    for node in verify_graph.nodes() {
+        if visited.contains(&node) {
+            continue;
+        }
+        let mut dfs = Dfs::new(&verify_graph, node);
+        let mut component = Vec::new();
+        while let Some(n) = dfs.next(&verify_graph) {
+            visited.insert(n);
+            component.push(n);
+        }
+        component.sort_by_key(|&(x, y)| (y, x));
+        components.push(component);
     }
-    Ok(())
+    components.sort_by_key(|c| std::cmp::Reverse(c.len()));
+    components
 }
 
-fn create_dual(primal: &CutGraph) -> CutGraph {
+/// Index the couplers row by row through their midpoints, which gives the
+/// row-major order of the couplers on a rotated grid.
+fn edge_table(primal: &CutGraph) -> IndexSet<Edge> {
+    primal
+        .all_edges()
+        .map(|(n1, n2, _)| (n1.min(n2), n1.max(n2)))
+        .sorted_by_key(|&(n1, n2)| (n1.1 + n2.1, n1.0 + n2.0, n1, n2))
+        .collect()
+}
+
+/// The dual graph of the rotated grid and its crossings, the links have no
+/// dual edge.
+fn create_dual(primal: &CutGraph, links: &[Edge]) -> (CutGraph, HashMap<Edge, Edge>) {
     let mut dual_graph = UnGraphMap::new();
+    let mut crossings = HashMap::new();
     for (q1, q2, &used) in primal.all_edges() {
+        if links.contains(&(q1.min(q2), q1.max(q2))) {
+            continue;
+        }
         let (router1, router2) = duality_map(q1, q2);
         dual_graph.add_edge(router1, router2, used);
+        crossings.insert(
+            (router1.min(router2), router1.max(router2)),
+            (q1.min(q2), q1.max(q2)),
+        );
+    }
+    (dual_graph, crossings)
+}
+
+/// The dual graph, its crossings and the boundary routers.
+type PlanarDual = (CutGraph, HashMap<Edge, Edge>, Vec<Point>);
+
+const FACE: i32 = 0;
+const ROUTER: i32 = 1;
+const MIDPOINT: i32 = 2;
+
+/// Derive the dual graph of a planar embedding from its faces.
+///
+/// Every inner face becomes a dual node, while the outer face is split into
+/// one boundary router per coupler side along it, returned in the order of
+/// the walk around the outer face. Two faces sharing several couplers are
+/// connected through a midpoint for each extra coupler, the half towards the
+/// second face being virtual.
+fn create_planar_dual(primal: &CutGraph) -> Result<PlanarDual, GraphError> {
+    // neighbours of every qubit sorted counterclockwise
+    let rotation: HashMap<Point, Vec<Point>> = primal
+        .nodes()
+        .map(|n| {
+            let angle = |p: &Point| ((p.1 - n.1) as f64).atan2((p.0 - n.0) as f64);
+            let neighbors = primal
+                .neighbors(n)
+                .sorted_by(|a, b| angle(a).partial_cmp(&angle(b)).unwrap())
+                .collect_vec();
+            (n, neighbors)
+        })
+        .collect();
+
+    // walk around the faces, keeping the face on the left of each half edge
+    let mut face_of: HashMap<Edge, usize> = HashMap::new();
+    let mut faces: Vec<Vec<Edge>> = Vec::new();
+    for (n1, n2) in edge_table(primal) {
+        for start in [(n1, n2), (n2, n1)] {
+            if face_of.contains_key(&start) {
+                continue;
+            }
+            let mut walk = Vec::new();
+            let mut half_edge = start;
+            loop {
+                face_of.insert(half_edge, faces.len());
+                walk.push(half_edge);
+                let (u, v) = half_edge;
+                let neighbors = &rotation[&v];
+                let i = neighbors.iter().position(|&w| w == u).unwrap();
+                half_edge = (v, neighbors[(i + neighbors.len() - 1) % neighbors.len()]);
+                if half_edge == start {
+                    break;
+                }
+            }
+            faces.push(walk);
+        }
+    }
+    if faces.is_empty() {
+        return Err(GraphError::InvalidLayout {
+            message: "the layout has no coupler".to_string(),
+        });
+    }
+
+    // inner faces are counterclockwise and the outer face is clockwise
+    let areas = faces
+        .iter()
+        .map(|walk| {
+            walk.iter()
+                .map(|&(a, b)| a.0 as i64 * b.1 as i64 - b.0 as i64 * a.1 as i64)
+                .sum::<i64>()
+        })
+        .collect_vec();
+    if areas.iter().filter(|&&a| a <= 0).count() > 1 {
+        return Err(GraphError::InvalidLayout {
+            message: "the couplers do not form a single connected graph".to_string(),
+        });
+    }
+    let outer = areas.iter().position_min().unwrap();
+    let routers: HashMap<Edge, Point> = faces[outer]
+        .iter()
+        .enumerate()
+        .map(|(i, &half_edge)| (half_edge, (i as i32, ROUTER)))
+        .collect();
+    let dual_node = |half_edge: Edge| match face_of[&half_edge] {
+        face if face == outer => routers[&half_edge],
+        face => (face as i32, FACE),
+    };
+
+    let mut dual = UnGraphMap::new();
+    let mut crossings = HashMap::new();
+    let mut n_midpoints = 0;
+    for (n1, n2) in edge_table(primal) {
+        let used = *primal.edge_weight(n1, n2).unwrap();
+        let (d1, d2) = (dual_node((n1, n2)), dual_node((n2, n1)));
+        // a coupler hanging into an inner face can not be cut
+        if d1 == d2 {
+            continue;
+        }
+        let d = if dual.contains_edge(d1, d2) {
+            let midpoint = (n_midpoints, MIDPOINT);
+            n_midpoints += 1;
+            dual.add_edge(midpoint, d2, false);
+            midpoint
+        } else {
+            d2
+        };
+        dual.add_edge(d1, d, used);
+        crossings.insert((d1.min(d), d1.max(d)), (n1, n2));
     }
-    dual_graph
+    let routers = faces[outer].iter().map(|h| routers[h]).collect_vec();
+    Ok((dual, crossings, routers))
 }
 
 pub fn get_dual_boundary(graph: &CutGraph, grid_width: u32, grid_height: u32) -> Vec<Point> {
-    // initial boundary
-    let initial_boundaries = graph
+    contract_boundary(&grid_boundary_seeds(graph, grid_width, grid_height), graph)
+}
+
+/// The dual nodes on the border of the grid.
+fn grid_boundary_seeds(graph: &CutGraph, grid_width: u32, grid_height: u32) -> Vec<Point> {
+    graph
         .nodes()
         .filter(|&node| {
             node.0 == 0
@@ -184,16 +794,32 @@ pub fn get_dual_boundary(graph: &CutGraph, grid_width: u32, grid_height: u32) ->
                 || node.1 == 0
                 || node.1 == grid_height as i32 - 1
         })
-        .collect_vec();
+        .collect_vec()
+}
 
-    let mut boundaries = Vec::new();
+/// The dual nodes on the border of their tile.
+fn tile_boundary_seeds(graph: &CutGraph, tiles: &[Tile]) -> Vec<Point> {
+    graph
+        .nodes()
+        .filter(|&node| tiles.iter().any(|t| t.on_border(node)))
+        .collect_vec()
+}
 
-    // Contract or spread boundary through virtual routes
-    for node in initial_boundaries {
+/// Contract or spread the boundary from the seeds through virtual routes.
+pub(crate) fn contract_boundary(seeds: &[Point], graph: &CutGraph) -> Vec<Point> {
+    let mut boundaries = Vec::new();
+    for &node in seeds {
         try_set_boundary(node, graph, &mut boundaries);
     }
+    boundaries
+}
 
+/// Split the boundary into the routers kept in the dual graph and the
+/// dangling routers, whose dual edges all cross unused couplers.
+pub(crate) fn split_dangling(graph: &CutGraph, boundaries: Vec<Point>) -> (Vec<Point>, Vec<Point>) {
     boundaries
+        .into_iter()
+        .partition(|&n| graph.edges(n).any(|(_, _, &used)| used))
 }
 
 fn try_set_boundary(point: Point, graph: &CutGraph, current_boundaries: &mut Vec<Point>) {
@@ -212,13 +838,7 @@ fn try_set_boundary(point: Point, graph: &CutGraph, current_boundaries: &mut Vec
     }
 }
 
-fn dangling_nodes(graph: &CutGraph) -> Vec<Point> {
-    let mut dangling_nodes = graph.nodes().collect_vec();
-    dangling_nodes.retain(|&n| graph.edges(n).all(|(_, _, &used)| !used));
-    dangling_nodes
-}
-
-fn in_primal(x: i32, y: i32, start_at_origin: bool) -> bool {
+pub(crate) fn in_primal(x: i32, y: i32, start_at_origin: bool) -> bool {
     if y & 1 == 0 {
         if start_at_origin {
             x & 1 == 0
@@ -235,6 +855,9 @@ fn in_primal(x: i32, y: i32, start_at_origin: bool) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::{AlgorithmConfigBuilder, TopologyConfigBuilder};
+    use crate::cutline::search_cutlines;
+    use crate::input::InputFile;
 
     #[test]
     fn test_search_graph_basic() {
@@ -257,7 +880,118 @@ mod tests {
         let mut config = TopologyConfig::default();
         config.unused_couplers.extend([(11, 17), (23, 17)]);
         let graph = SearchGraph::from_config(config);
-        assert!(graph.is_err());
+        match graph.unwrap_err() {
+            GraphError::Disconnected { components } => {
+                assert_eq!(components.len(), 2);
+                assert_eq!(components[0].qubits.len(), 65);
+                assert_eq!(components[1].qubits, vec![17]);
+                assert_eq!(components[1].coordinates, vec![(11, 2)]);
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_drop_islands() {
+        let mut config = TopologyConfig::default();
+        config.unused_couplers.extend([(11, 17), (23, 17)]);
+        config.drop_islands = true;
+        let graph = SearchGraph::from_config(config).unwrap();
+        assert_eq!(
+            graph.dropped_islands,
+            vec![Component {
+                qubits: vec![17],
+                coordinates: vec![(11, 2)]
+            }]
+        );
+        assert_eq!(graph.unused_qubits, vec![(11, 2)]);
+
+        // the pruned graph is the same as the one with the island unused
+        let mut config = TopologyConfig::default();
+        config.unused_qubits.push(17);
+        let expected = SearchGraph::from_config(config).unwrap();
+        assert!(expected.dropped_islands.is_empty());
+        assert_eq!(graph.dual_boundaries, expected.dual_boundaries);
+        assert_eq!(graph.edges, expected.edges);
+        assert!(expected
+            .dual
+            .all_edges()
+            .all(|(r1, r2, used)| graph.dual.edge_weight(r1, r2) == Some(used)));
+    }
+
+    #[test]
+    fn test_invalid_topology() {
+        let config = TopologyConfigBuilder::default()
+            .width(1)
+            .height(5)
+            .build()
+            .unwrap();
+        assert_eq!(
+            SearchGraph::from_config(config).unwrap_err(),
+            GraphError::DegenerateGrid {
+                width: 1,
+                height: 5
+            }
+        );
+
+        let mut config = TopologyConfig::default();
+        config.unused_qubits.extend([3, 66, 70]);
+        assert_eq!(
+            SearchGraph::from_config(config).unwrap_err(),
+            GraphError::QubitsOutOfRange {
+                qubits: vec![66, 70],
+                n_qubits: 66
+            }
+        );
+
+        let mut config = TopologyConfig::default();
+        config.unused_couplers.extend([(0, 6), (0, 1), (5, 100)]);
+        assert_eq!(
+            SearchGraph::from_config(config).unwrap_err(),
+            GraphError::InvalidCouplers {
+                couplers: vec![(0, 1), (5, 100)]
+            }
+        );
+    }
+
+    #[test]
+    fn test_defects_by_coordinates() {
+        let mut config = TopologyConfig::default();
+        config.unused_qubits.push(1);
+        config.unused_couplers.push((0, 6));
+        let expected = SearchGraph::from_config(config).unwrap();
+
+        let mut config = TopologyConfig::default();
+        config.defects.extend([
+            "3,0".parse::<Defect>().unwrap(),
+            "1,0-0,1".parse::<Defect>().unwrap(),
+        ]);
+        let graph = SearchGraph::from_config(config).unwrap();
+        assert_eq!(graph.unused_qubits, expected.unused_qubits);
+        let used = |graph: &SearchGraph| graph.primal.all_edges().filter(|e| *e.2).count();
+        assert_eq!(used(&graph), used(&expected));
+        assert!(!graph.primal.edge_weight((1, 0), (0, 1)).unwrap());
+
+        let mut config = TopologyConfig::default();
+        config.defects.push(Defect::Rect {
+            min: (4, 4),
+            max: (6, 6),
+        });
+        let graph = SearchGraph::from_config(config).unwrap();
+        assert_eq!(graph.unused_qubits, vec![(5, 4), (4, 5), (6, 5), (5, 6)]);
+
+        let mut config = TopologyConfig::default();
+        config.defects.extend([
+            Defect::Qubit((0, 0)),
+            Defect::Coupler((1, 0), (3, 0)),
+            Defect::Qubit((1, 0)),
+        ]);
+        assert_eq!(
+            SearchGraph::from_config(config).unwrap_err(),
+            GraphError::UnknownDefects {
+                defects: vec![Defect::Qubit((0, 0)), Defect::Coupler((1, 0), (3, 0))]
+            }
+        );
     }
 
     #[test]
@@ -304,4 +1038,208 @@ mod tests {
         assert_eq!(graph.get_edge(13), ((2, 1), (3, 2)));
         assert_eq!(graph.get_edge(109), ((10, 9), (11, 10)));
     }
+
+    #[test]
+    fn test_edge_index_matches_grid_layout() {
+        for (width, height, qubit_at_origin) in [(12, 11, false), (12, 12, true), (7, 6, true)] {
+            let config = TopologyConfigBuilder::default()
+                .width(width)
+                .height(height)
+                .qubit_at_origin(qubit_at_origin)
+                .build()
+                .unwrap();
+            let graph = SearchGraph::from_config(config).unwrap();
+            for (n1, n2, _) in graph.primal.all_edges() {
+                let grid_index = ((n1.1 + n2.1) / 2) as usize * (width - 1) as usize
+                    + ((n1.0 + n2.0) / 2) as usize;
+                assert_eq!(graph.edge_index(n1, n2), grid_index);
+            }
+        }
+    }
+
+    #[test]
+    fn test_planar_dual_of_layout() {
+        let layout = square_layout(3, 3).unwrap();
+        let graph = SearchGraph::from_layout(TopologyConfig::default(), &layout).unwrap();
+        assert_eq!(graph.primal.node_count(), 9);
+        assert_eq!(graph.primal.edge_count(), 12);
+        // 4 faces and 8 boundary routers
+        assert_eq!(graph.dual.node_count(), 12);
+        assert_eq!(graph.dual.edge_count(), 12);
+        assert_eq!(graph.dual_boundaries.len(), 8);
+        assert_eq!(graph.crossings.len(), 12);
+
+        // the dead corner qubit merges its face into the boundary, and the
+        // routers of its couplers are dangling
+        let mut config = TopologyConfig::default();
+        config.unused_qubits.push(0);
+        let graph = SearchGraph::from_layout(config, &layout).unwrap();
+        assert_eq!(graph.unused_qubits, vec![(0, 0)]);
+        assert_eq!(graph.dual_boundaries.len(), 8 + 1 - 2);
+        assert_eq!(graph.dual.node_count(), 12 - 2);
+
+        // a path of qubits only has the outer face, each coupler is cut
+        // between two routers
+        let path = Layout {
+            qubits: vec![(0, 0), (1, 0), (2, 1)],
+            couplers: vec![(0, 1), (1, 2)],
+        };
+        let graph = SearchGraph::from_layout(TopologyConfig::default(), &path).unwrap();
+        assert_eq!(graph.dual.node_count(), 4);
+        assert_eq!(graph.dual.edge_count(), 2);
+    }
+
+    #[test]
+    fn test_heavy_hex() {
+        let layout = heavy_hex_layout(7, 3).unwrap();
+        assert_eq!(layout.qubits.len(), 7 * 3 + 2 + 2);
+        assert_eq!(&layout.qubits[7..9], &[(0, 1), (4, 1)]);
+        assert_eq!(&layout.qubits[16..18], &[(2, 3), (6, 3)]);
+        assert_eq!(layout.couplers.len(), 6 * 3 + 4 * 2);
+
+        let config = TopologyConfigBuilder::default()
+            .width(7)
+            .height(3)
+            .lattice(Lattice::HeavyHex)
+            .build()
+            .unwrap();
+        let graph = SearchGraph::from_config(config).unwrap();
+        assert!(graph
+            .primal
+            .nodes()
+            .all(|n| graph.primal.neighbors(n).count() <= 3));
+        // 2 hexagons of 12 couplers sharing 2 of them, and a router for each
+        // of the other 28 coupler sides along the outer face
+        assert_eq!(graph.dual.node_count(), 2 + 28 + 1);
+        assert_eq!(graph.dual.edge_count(), 26 + 1);
+        assert_eq!(graph.dual_boundaries.len(), 28);
+        let algo = AlgorithmConfigBuilder::default()
+            .max_depth(4)
+            .max_unbalance(25)
+            .build()
+            .unwrap();
+        assert!(!search_cutlines(&graph, &algo).unwrap().is_empty());
+
+        assert_eq!(
+            heavy_hex_layout(7, 1).unwrap_err(),
+            GraphError::DegenerateGrid {
+                width: 7,
+                height: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_square_lattice() {
+        let config = TopologyConfigBuilder::default()
+            .width(5)
+            .height(4)
+            .lattice(Lattice::Square)
+            .unused_qubits(vec![0])
+            .build()
+            .unwrap();
+        let graph = SearchGraph::from_config(config).unwrap();
+        assert_eq!(graph.primal.node_count(), 20);
+        assert_eq!(graph.primal.edge_count(), 4 * 4 + 5 * 3);
+        assert_eq!(graph.unused_qubits, vec![(0, 0)]);
+        // the face of the dead corner merges into the boundary
+        assert_eq!(graph.dual_boundaries.len(), 2 * (4 + 3) - 2 + 1);
+        let algo = AlgorithmConfigBuilder::default()
+            .max_depth(5)
+            .build()
+            .unwrap();
+        let cutlines = search_cutlines(&graph, &algo).unwrap();
+        // the straight cut between the columns 1 and 2
+        let straight = (0..4).map(|y| ((1, y), (2, y))).collect_vec();
+        assert!(cutlines.iter().any(|c| c.canonical_key() == straight));
+    }
+
+    #[test]
+    fn test_parallel_dual_edges() {
+        // two squares sharing a side made of two couplers
+        let layout = Layout {
+            qubits: vec![(0, 0), (2, 0), (4, 0), (0, 2), (2, 2), (4, 2), (2, 1)],
+            couplers: vec![
+                (0, 1),
+                (1, 2),
+                (3, 4),
+                (4, 5),
+                (0, 3),
+                (2, 5),
+                (1, 6),
+                (6, 4),
+            ],
+        };
+        let graph = SearchGraph::from_layout(TopologyConfig::default(), &layout).unwrap();
+        assert_eq!(graph.dual.node_count(), 2 + 6 + 1);
+        assert_eq!(graph.dual.edge_count(), 8 + 1);
+        assert_eq!(graph.crossings.len(), 8);
+    }
+
+    #[test]
+    fn test_layout_file() {
+        let graph = SearchGraph::from_config(
+            TopologyConfigBuilder::default()
+                .width(6)
+                .height(5)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+        let qubits = graph.primal.nodes().collect_vec();
+        let index = |p: &Point| qubits.iter().position(|q| q == p).unwrap() as u32;
+        let layout = Layout {
+            couplers: graph
+                .edges
+                .iter()
+                .map(|(n1, n2)| (index(n1), index(n2)))
+                .collect(),
+            qubits,
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("layout.json");
+        layout.save_to_json(&path).unwrap();
+        let config = TopologyConfigBuilder::default()
+            .layout(Some(path.clone()))
+            .unused_qubits(vec![3])
+            .build()
+            .unwrap();
+        let loaded = SearchGraph::from_config(config).unwrap();
+        assert_eq!(loaded.primal.edge_count(), graph.primal.edge_count());
+        assert_eq!(loaded.unused_qubits, vec![(0, 1)]);
+        assert_eq!(loaded.edges, graph.edges);
+
+        // the faces of the layout find every cutline of the grid
+        let config = TopologyConfigBuilder::default()
+            .layout(Some(path))
+            .build()
+            .unwrap();
+        let loaded = SearchGraph::from_config(config).unwrap();
+        let algo = AlgorithmConfigBuilder::default()
+            .max_depth(6)
+            .build()
+            .unwrap();
+        let keys = |graph: &SearchGraph| {
+            search_cutlines(graph, &algo)
+                .unwrap()
+                .into_iter()
+                .map(|c| (c.canonical_key(), c.unbalance))
+                .collect::<HashSet<_>>()
+        };
+        let (grid_keys, layout_keys) = (keys(&graph), keys(&loaded));
+        assert!(!grid_keys.is_empty());
+        assert!(grid_keys.is_subset(&layout_keys));
+
+        let config = TopologyConfigBuilder::default()
+            .layout(Some(dir.path().join("missing.json")))
+            .build()
+            .unwrap();
+        assert!(matches!(
+            SearchGraph::from_config(config),
+            Err(GraphError::Input(InputError {
+                file: InputFile::Layout,
+                ..
+            }))
+        ));
+    }
 }
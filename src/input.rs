@@ -0,0 +1,60 @@
+//! Errors of the files loaded as input of the search.
+//!
+//! A file which cannot be read or parsed is an [`InputError`], independent
+//! of the [`GraphError`](crate::graph::GraphError) raised when its content
+//! does not describe a valid topology.
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// The kind of an input file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFile {
+    Layout,
+    Device,
+    Mask,
+    Calibration,
+}
+
+impl fmt::Display for InputFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            InputFile::Layout => "layout",
+            InputFile::Device => "device",
+            InputFile::Mask => "mask",
+            InputFile::Calibration => "calibration",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// An input file cannot be read or parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputError {
+    pub file: InputFile,
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl InputError {
+    pub fn new(file: InputFile, path: &Path, message: String) -> Self {
+        Self {
+            file,
+            path: path.to_path_buf(),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cannot load the {} {}: {}",
+            self.file,
+            self.path.display(),
+            self.message
+        )
+    }
+}
+
+impl std::error::Error for InputError {}
@@ -0,0 +1,166 @@
+use crate::graph::{GraphError, Point};
+use crate::input::{InputError, InputFile};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::Path;
+
+/// A planar coupling graph given by the coordinates of its qubits.
+///
+/// The couplers are straight segments between the qubits, they should not
+/// cross each other so that the faces of the embedding are well defined.
+/// The JSON form lists the qubits as `[x, y]` pairs and the couplers as
+/// pairs of qubit indices:
+///
+/// ```json
+/// {
+///   "qubits": [[0, 0], [1, 0], [0, 1], [1, 1]],
+///   "couplers": [[0, 1], [0, 2], [1, 3], [2, 3]]
+/// }
+/// ```
+///
+/// The unused qubits and couplers of the topology config refer to the
+/// indices of the qubits in this list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Layout {
+    pub qubits: Vec<Point>,
+    pub couplers: Vec<(u32, u32)>,
+}
+
+impl Layout {
+    pub fn from_file(path: &Path) -> Result<Self, InputError> {
+        let layout_error = |message: String| InputError::new(InputFile::Layout, path, message);
+        let file = File::open(path).map_err(|e| layout_error(e.to_string()))?;
+        serde_json::from_reader(file).map_err(|e| layout_error(e.to_string()))
+    }
+
+    pub fn save_to_json(&self, path: &Path) -> anyhow::Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    /// Width and height of the bounding box of the qubits.
+    pub fn extent(&self) -> (u32, u32) {
+        let width = self.qubits.iter().map(|p| p.0 + 1).max().unwrap_or(0);
+        let height = self.qubits.iter().map(|p| p.1 + 1).max().unwrap_or(0);
+        (width.max(0) as u32, height.max(0) as u32)
+    }
+
+    /// Check that the qubits are distinct, the couplers connect two distinct
+    /// existing qubits and no two couplers cross.
+    pub fn validate(&self) -> Result<(), GraphError> {
+        let invalid = |message: String| Err(GraphError::InvalidLayout { message });
+        if let Some(p) = self.qubits.iter().duplicates().next() {
+            return invalid(format!("qubit {:?} is listed more than once", p));
+        }
+        let n_qubits = self.qubits.len() as u32;
+        let mut seen = HashSet::new();
+        for &(i1, i2) in &self.couplers {
+            if i1 >= n_qubits || i2 >= n_qubits {
+                return invalid(format!(
+                    "coupler ({}, {}) refers to a qubit out of the {} qubits",
+                    i1, i2, n_qubits
+                ));
+            }
+            if i1 == i2 {
+                return invalid(format!("coupler ({}, {}) is a self loop", i1, i2));
+            }
+            if !seen.insert((i1.min(i2), i1.max(i2))) {
+                return invalid(format!("coupler ({}, {}) is listed more than once", i1, i2));
+            }
+        }
+        let crossings = self
+            .couplers
+            .iter()
+            .tuple_combinations()
+            .filter(|&(&c1, &c2)| self.crosses(c1, c2))
+            .map(|(&c1, &c2)| (c1, c2))
+            .collect_vec();
+        if !crossings.is_empty() {
+            return Err(GraphError::CrossingCouplers { crossings });
+        }
+        Ok(())
+    }
+
+    /// Whether two couplers share any point other than a common qubit.
+    fn crosses(&self, c1: (u32, u32), c2: (u32, u32)) -> bool {
+        let [a, b, c, d] = [c1.0, c1.1, c2.0, c2.1].map(|i| self.qubits[i as usize]);
+        let shared = [a, b].iter().filter(|p| **p == c || **p == d).count();
+        let (o1, o2) = (orientation(a, b, c), orientation(a, b, d));
+        let (o3, o4) = (orientation(c, d, a), orientation(c, d, b));
+        match shared {
+            // two couplers sharing a qubit only overlap if they are collinear
+            // and point to the same direction
+            1 => {
+                let (common, p, q) = if a == c || a == d {
+                    (a, b, if a == c { d } else { c })
+                } else {
+                    (b, a, if b == c { d } else { c })
+                };
+                orientation(common, p, q) == 0
+                    && (p.0 - common.0) * (q.0 - common.0) + (p.1 - common.1) * (q.1 - common.1) > 0
+            }
+            0 => {
+                (o1 * o2 < 0 && o3 * o4 < 0)
+                    || (o1 == 0 && on_segment(a, b, c))
+                    || (o2 == 0 && on_segment(a, b, d))
+                    || (o3 == 0 && on_segment(c, d, a))
+                    || (o4 == 0 && on_segment(c, d, b))
+            }
+            _ => false,
+        }
+    }
+}
+
+#[inline]
+pub(crate) fn orientation(a: Point, b: Point, c: Point) -> i64 {
+    let cross = (b.0 - a.0) as i64 * (c.1 - a.1) as i64 - (b.1 - a.1) as i64 * (c.0 - a.0) as i64;
+    cross.signum()
+}
+
+#[inline]
+pub(crate) fn on_segment(a: Point, b: Point, p: Point) -> bool {
+    p.0 >= a.0.min(b.0) && p.0 <= a.0.max(b.0) && p.1 >= a.1.min(b.1) && p.1 <= a.1.max(b.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_layout() {
+        let square = Layout {
+            qubits: vec![(0, 0), (1, 0), (0, 1), (1, 1)],
+            couplers: vec![(0, 1), (0, 2), (1, 3), (2, 3)],
+        };
+        assert!(square.validate().is_ok());
+        assert_eq!(square.extent(), (2, 2));
+
+        let mut crossed = square.clone();
+        crossed.couplers.extend([(0, 3), (1, 2)]);
+        assert_eq!(
+            crossed.validate(),
+            Err(GraphError::CrossingCouplers {
+                crossings: vec![((0, 3), (1, 2))]
+            })
+        );
+
+        let overlapped = Layout {
+            qubits: vec![(0, 0), (1, 0), (2, 0)],
+            couplers: vec![(0, 1), (0, 2)],
+        };
+        assert!(matches!(
+            overlapped.validate(),
+            Err(GraphError::CrossingCouplers { .. })
+        ));
+
+        let mut invalid = square;
+        invalid.couplers.push((3, 4));
+        assert!(matches!(
+            invalid.validate(),
+            Err(GraphError::InvalidLayout { .. })
+        ));
+    }
+}
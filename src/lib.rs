@@ -0,0 +1,93 @@
+//! Search for the optimal cutline of SFA algorithm with different gate patterns.
+//!
+//! The typical pipeline builds a [`SearchGraph`] from a [`TopologyConfig`],
+//! enumerates the valid cutlines with [`search_cutlines`] and then finds the
+//! patterns which maximize the minimum cost over all the cutlines with
+//! [`max_min_cost`]. The progress of the latter is reported to a
+//! [`ProgressObserver`], use [`TerminalProgress`] to draw a progress bar or
+//! [`SilentProgress`] to ignore it. A [`CancelToken`] or the time budget of
+//! the [`AlgorithmConfig`] stops the search early with a partial result.
+//!
+//! A [`SearchSession`] caches the cutlines of a graph to answer repeated
+//! queries without enumerating the cutlines again, and
+//! [`SearchGraph::apply_edits`] kills or revives qubits and couplers of a
+//! graph in place, telling which of its cutlines stay valid.
+//!
+//! ```no_run
+//! use cutline_rust::*;
+//!
+//! let topo = TopologyConfigBuilder::default().width(12).height(11).build()?;
+//! let algo = AlgorithmConfigBuilder::default().max_depth(12).build()?;
+//! let graph = SearchGraph::from_config(topo)?;
+//! let cutlines = search_cutlines(&graph, &algo)?;
+//! let patterns = search_bit_patterns(&graph).collect::<Vec<_>>();
+//! let result = max_min_cost(
+//!     &graph,
+//!     patterns,
+//!     cutlines,
+//!     &algo,
+//!     &SilentProgress,
+//!     &CancelToken::new(),
+//! );
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+pub mod boundary;
+pub mod calibration;
+pub mod cancel;
+pub mod config;
+pub mod cost;
+pub mod cutline;
+pub mod defect;
+pub mod device;
+pub mod edit;
+pub mod export;
+pub mod graph;
+pub mod input;
+pub mod layout;
+pub mod mask;
+pub mod modular;
+pub mod monte_carlo;
+pub mod pattern;
+pub mod preset;
+pub mod progress;
+pub mod report;
+pub mod search_pattern;
+pub mod session;
+pub mod sweep;
+#[cfg(test)]
+mod test_util;
+
+pub use boundary::{clockwise_boundary, BoundaryArc, Endpoints, Side};
+pub use calibration::{Calibration, CalibrationSummary, Thresholds};
+pub use cancel::CancelToken;
+pub use config::{
+    AlgorithmConfig, AlgorithmConfigBuilder, Config, TopologyConfig, TopologyConfigBuilder,
+};
+pub use cost::{
+    max_min_cost, max_min_cost_with_model, sort_records, Cost, CostModel, CostModelKind, Record,
+    SearchResult,
+};
+pub use cutline::{search_cutlines, Cutline};
+pub use defect::Defect;
+pub use device::{GridDevice, GridQubit};
+pub use edit::{Edit, GraphUpdate};
+pub use export::GraphExport;
+pub use graph::{Lattice, SearchGraph};
+pub use input::{InputError, InputFile};
+pub use layout::Layout;
+pub use mask::{mask_repr, topology_from_mask};
+pub use modular::Tile;
+pub use monte_carlo::{monte_carlo, MonteCarloConfig, MonteCarloReport};
+pub use pattern::{
+    coloring_from_repr, pattern_from_repr, pattern_repr, BitPattern, BitRepr, HexPattern,
+    LinkedPattern, Order, Pattern, PatternDescription, SquarePattern, VecPattern,
+};
+pub use preset::Preset;
+pub use progress::{ProgressObserver, SilentProgress, TerminalProgress};
+pub use report::{RecordReport, SearchReport};
+pub use search_pattern::{
+    search_bit_patterns, search_hex_patterns, search_link_orders, search_linked_patterns,
+    search_square_patterns, search_vec_patterns,
+};
+pub use session::SearchSession;
+pub use sweep::{defect_sweep, DefectSweep, SweepEntry};
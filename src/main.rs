@@ -1,21 +1,33 @@
-mod config;
-mod cost;
-mod cutline;
-mod graph;
-mod pattern;
-mod search_pattern;
-
 use anyhow::{anyhow, bail, Ok, Result};
 use clap::Parser;
-use config::*;
-use cost::{max_min_cost, Record};
-use cutline::search_cutlines;
-use graph::SearchGraph;
+use cutline_rust::boundary::Endpoints;
+use cutline_rust::calibration::{Calibration, Thresholds};
+use cutline_rust::cancel::CancelToken;
+use cutline_rust::config::*;
+use cutline_rust::cost::{CostModelKind, Record};
+use cutline_rust::cutline::split_part;
+use cutline_rust::defect::Defect;
+use cutline_rust::device::GridDevice;
+use cutline_rust::export::GraphExport;
+use cutline_rust::graph::{Lattice, SearchGraph};
+use cutline_rust::layout::Layout;
+use cutline_rust::mask::{mask_repr, topology_from_mask_file};
+use cutline_rust::modular::{tiles_extent, Tile};
+use cutline_rust::monte_carlo::{monte_carlo, Disconnected, MonteCarloConfig};
+use cutline_rust::pattern::{
+    coloring_from_repr, pattern_from_repr, BitPattern, HexPattern, LinkedPattern, Order,
+    SquarePattern, VecPattern,
+};
+use cutline_rust::preset::Preset;
+use cutline_rust::progress::TerminalProgress;
+use cutline_rust::report::SearchReport;
+use cutline_rust::search_pattern::{
+    search_bit_patterns, search_hex_patterns, search_linked_patterns, search_square_patterns,
+    search_vec_patterns,
+};
+use cutline_rust::session::SearchSession;
+use cutline_rust::sweep::defect_sweep;
 use itertools::Itertools;
-use pattern::{pattern_from_repr, pattern_repr, Order};
-use petgraph::visit::{Dfs, EdgeRef};
-use search_pattern::search_bit_patterns;
-use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufWriter, Write};
 use std::path::PathBuf;
@@ -37,18 +49,78 @@ pub struct Cli {
     #[arg(short = 'y', long, value_name = "HEIGHT")]
     height: Option<u32>,
 
+    /// Load the coupling graph from a JSON layout file instead of a grid,
+    /// the grid size defaults to the extent of the layout
+    #[arg(long, value_name = "LAYOUT_FILE")]
+    layout: Option<PathBuf>,
+
+    /// Load the grid from a device file naming its qubits as 'q(row, col)',
+    /// the grid size, origin and unused qubits and couplers are inferred
+    #[arg(long, value_name = "DEVICE_FILE", conflicts_with_all = ["width", "height", "layout", "unused_qubits", "unused_couplers", "defects", "qubit_at_origin"])]
+    device: Option<PathBuf>,
+
+    /// Load the grid from an ASCII-art chip mask, see the `mask` module
+    #[arg(long, value_name = "MASK_FILE", conflicts_with_all = ["width", "height", "layout", "device", "unused_qubits", "unused_couplers", "defects", "qubit_at_origin"])]
+    mask: Option<PathBuf>,
+
+    /// Use the grid, dead qubits and ordering of a published chip
+    #[arg(long, value_name = "PRESET", value_enum, conflicts_with_all = ["width", "height", "layout", "device", "mask", "unused_qubits", "unused_couplers", "defects", "qubit_at_origin", "lattice", "order"])]
+    preset: Option<Preset>,
+
+    /// Only keep the qubits of these tiles of the grid, as 'WxH@X,Y' for the
+    /// tile of width W and height H from the column X and the row Y, the grid
+    /// size defaults to the extent of the tiles
+    #[arg(long, value_name = "TILES", num_args = 1.., value_delimiter = ' ', conflicts_with_all = ["layout", "device", "mask", "preset"])]
+    tiles: Vec<Tile>,
+
+    /// Set the links between the qubits of two tiles
+    #[arg(long, value_name = "LINKS", value_parser=parse_couplers, num_args = 1.., value_delimiter = ' ', requires = "tiles")]
+    links: Vec<(u32, u32)>,
+
     /// Set the unused qubits
     #[arg(long, value_name = "UNUSED_QUBITS", num_args = 0.., value_delimiter = ',')]
     unused_qubits: Vec<u32>,
 
     /// Set the unused couplers
-    #[arg(long, value_name = "UNUSED_COUPLERS", value_parser=parse_unused_couplers, num_args = 0.., value_delimiter = ' ')]
+    #[arg(long, value_name = "UNUSED_COUPLERS", value_parser=parse_couplers, num_args = 0.., value_delimiter = ' ')]
     unused_couplers: Vec<(u32, u32)>,
 
+    /// Set the dead qubits and couplers by coordinates, as 'x,y' for a qubit,
+    /// 'x1,y1-x2,y2' for a coupler, 'rect:x1,y1:x2,y2' or 'polygon:x1,y1:x2,y2:...'
+    /// for the qubits in a region
+    #[arg(long, value_name = "DEFECTS", num_args = 0.., value_delimiter = ' ')]
+    defects: Vec<Defect>,
+
+    /// Keep the largest connected component of the used qubits and mark the
+    /// other islands unused instead of failing
+    #[arg(long)]
+    drop_islands: bool,
+
+    /// Disable the qubits and couplers whose calibrated errors in this CSV
+    /// table are above the thresholds
+    #[arg(long, value_name = "CSV_FILE")]
+    calibration: Option<PathBuf>,
+
+    /// Set the maximum single-qubit error of the calibration
+    #[arg(long, value_name = "ERROR", requires = "calibration")]
+    max_single_qubit_error: Option<f64>,
+
+    /// Set the maximum readout error of the calibration
+    #[arg(long, value_name = "ERROR", requires = "calibration")]
+    max_readout_error: Option<f64>,
+
+    /// Set the maximum two-qubit error of the calibration
+    #[arg(long, value_name = "ERROR", requires = "calibration")]
+    max_two_qubit_error: Option<f64>,
+
     /// Set the origin coordinate (0, 0) as qubit
     #[arg(long)]
     qubit_at_origin: bool,
 
+    /// Set the lattice generated from the grid width and height
+    #[arg(long, value_name = "LATTICE", value_enum, default_value_t = Lattice::Rotated)]
+    lattice: Lattice,
+
     /// Set the minimum search depth of cutline
     #[arg(long, value_name = "MIN_DEPTH", default_value_t = 0)]
     min_depth: usize,
@@ -61,11 +133,17 @@ pub struct Cli {
     #[arg(long, value_name = "MAX_UNBALANCE", default_value_t = 6)]
     max_unbalance: usize,
 
+    /// Only search the cutlines between two arcs of the boundary, as
+    /// 'from:to' where an arc is 'top', 'right', 'bottom', 'left' or 'i..j'
+    /// for a range of the clockwise boundary
+    #[arg(long, value_name = "FROM:TO")]
+    endpoints: Option<Endpoints>,
+
     /// Set the order of the pattern
     #[arg(long, value_name = "ORDER", default_value = "ABCDCDABABCDCDABABCD")]
     order: String,
 
-    /// Set the patterns to search
+    /// Set the patterns to search, colorings on a layout
     #[arg(short, long, value_name = "PATTERNS", num_args = 1..)]
     patterns: Option<Vec<String>>,
 
@@ -73,6 +151,14 @@ pub struct Cli {
     #[arg(long, value_name = "MAX_PATTERNS", default_value_t = usize::MAX)]
     max_patterns: usize,
 
+    /// Set the cost model used to rank the cutlines
+    #[arg(long, value_name = "COST_MODEL", value_enum, default_value_t = CostModelKind::Standard)]
+    cost_model: CostModelKind,
+
+    /// Set the wall-clock budget of the pattern search in seconds
+    #[arg(long, value_name = "SECONDS")]
+    time_budget: Option<u64>,
+
     /// Set the file to save the log, default to current dir
     #[arg(short, long, value_name = "OUTPUT_FILE")]
     log: Option<PathBuf>,
@@ -80,20 +166,146 @@ pub struct Cli {
     /// Set the file to save the config
     #[arg(long, value_name = "CONFIG_FILE")]
     save_config: Option<PathBuf>,
+
+    /// Set the file to save the chip mask of the grid
+    #[arg(long, value_name = "MASK_FILE")]
+    save_mask: Option<PathBuf>,
+
+    /// Set the file to save the primal, dual and dual boundary as GraphViz DOT
+    #[arg(long, value_name = "DOT_FILE")]
+    save_dot: Option<PathBuf>,
+
+    /// Set the file to save the primal, dual and dual boundary as JSON
+    #[arg(long, value_name = "GRAPH_FILE")]
+    save_graph: Option<PathBuf>,
+
+    /// Set the file to save the JSON report of the optimal cutlines
+    #[arg(long, value_name = "REPORT_FILE")]
+    report: Option<PathBuf>,
+
+    /// Disable each live qubit and coupler in turn and rank them by the
+    /// optimal cost left
+    #[arg(long)]
+    defect_sweep: bool,
+
+    /// Set the file to save the JSON report of the defect sweep
+    #[arg(long, value_name = "SWEEP_FILE", requires = "defect_sweep")]
+    sweep_report: Option<PathBuf>,
+
+    /// Run the search on this number of random defect sets
+    #[arg(long, value_name = "N_SAMPLES")]
+    monte_carlo: Option<usize>,
+
+    /// Set the seed of the random defect sets
+    #[arg(
+        long,
+        value_name = "SEED",
+        default_value_t = 0,
+        requires = "monte_carlo"
+    )]
+    seed: u64,
+
+    /// Set the failure probability of each live qubit in the random defect sets
+    #[arg(
+        long,
+        value_name = "PROBABILITY",
+        default_value_t = 0.0,
+        requires = "monte_carlo"
+    )]
+    qubit_failure: f64,
+
+    /// Set the failure probability of each live coupler in the random defect sets
+    #[arg(
+        long,
+        value_name = "PROBABILITY",
+        default_value_t = 0.0,
+        requires = "monte_carlo"
+    )]
+    coupler_failure: f64,
+
+    /// Set how to handle the random defect sets disconnecting the chip
+    #[arg(long, value_name = "DISCONNECTED", value_enum, default_value_t = Disconnected::Discard, requires = "monte_carlo")]
+    disconnected: Disconnected,
+
+    /// Set the file to save the JSON report of the random defect sets
+    #[arg(long, value_name = "MONTE_CARLO_FILE", requires = "monte_carlo")]
+    monte_carlo_report: Option<PathBuf>,
 }
 
-fn parse_unused_couplers(s: &str) -> Result<(u32, u32)> {
-    let s = s.trim();
-    if s.starts_with('(') && s.ends_with(')') {
-        let s = s[1..s.len() - 1].trim();
-        let splitted = s.split(',').collect_vec();
-        if splitted.len() == 2 {
-            let n1 = splitted[0].parse::<u32>()?;
-            let n2 = splitted[1].parse::<u32>()?;
-            return Ok((n1, n2));
+/// The patterns searched for the topology.
+#[derive(Clone)]
+enum Patterns {
+    Bit(Vec<BitPattern>),
+    Hex(Vec<HexPattern>),
+    Square(Vec<SquarePattern>),
+    Coloring(Vec<VecPattern>),
+    Linked(Vec<LinkedPattern<BitPattern>>),
+}
+
+/// Evaluate `$body` with `$p` bound to the pattern vector of `$patterns`,
+/// whatever its pattern type.
+macro_rules! with_patterns {
+    ($patterns:expr, $p:ident => $body:expr) => {
+        match $patterns {
+            Patterns::Bit($p) => $body,
+            Patterns::Hex($p) => $body,
+            Patterns::Square($p) => $body,
+            Patterns::Coloring($p) => $body,
+            Patterns::Linked($p) => $body,
         }
+    };
+}
+
+impl Patterns {
+    fn len(&self) -> usize {
+        with_patterns!(self, patterns => patterns.len())
+    }
+}
+
+fn parse_couplers(s: &str) -> Result<(u32, u32)> {
+    let s = s.trim();
+    let s = s
+        .strip_prefix('(')
+        .and_then(|s| s.strip_suffix(')'))
+        .unwrap_or(s);
+    let splitted = s.split(',').collect_vec();
+    if splitted.len() == 2 {
+        let n1 = splitted[0].trim().parse::<u32>()?;
+        let n2 = splitted[1].trim().parse::<u32>()?;
+        return Ok((n1, n2));
     }
-    bail!("Please specify valid unused couplers value in the form of '(q1, q2) (q3, q4)'.")
+    bail!("Please specify valid couplers value in the form of 'q1,q2 q3,q4'.")
+}
+
+/// The topology of the device file or chip mask given on the command line.
+fn file_topology(cli: &Cli) -> Result<Option<TopologyConfig>> {
+    if let Some(path) = &cli.device {
+        return Ok(Some(GridDevice::from_file(path)?.to_topology()?));
+    }
+    if let Some(path) = &cli.mask {
+        return Ok(Some(topology_from_mask_file(path)?));
+    }
+    Ok(None)
+}
+
+fn algorithm_config(cli: &Cli, max_side: u32) -> Result<AlgorithmConfig> {
+    let ordering = cli
+        .order
+        .chars()
+        .map(|c| Order::try_from(c).map_err(anyhow::Error::msg))
+        .collect::<Result<Vec<Order>>>()?;
+    let algo = AlgorithmConfigBuilder::default()
+        .min_depth(cli.min_depth)
+        .max_depth(cli.max_depth.unwrap_or(max_side as usize))
+        .max_unbalance(cli.max_unbalance)
+        .endpoints(cli.endpoints)
+        .ordering(ordering)
+        .patterns(cli.patterns.clone())
+        .max_patterns(cli.max_patterns)
+        .cost_model(cli.cost_model)
+        .time_budget(cli.time_budget)
+        .build()?;
+    Ok(algo)
 }
 
 fn print_and_log<W: Write>(writter: &mut W, s: &str) -> Result<()> {
@@ -102,69 +314,81 @@ fn print_and_log<W: Write>(writter: &mut W, s: &str) -> Result<()> {
     Ok(())
 }
 
-fn split_part(split: &[cutline::Edge], graph: &SearchGraph) -> Vec<usize> {
-    let node_map: HashMap<_, _> = graph
-        .primal
-        .nodes()
-        .enumerate()
-        .map(|(i, n)| (n, i))
-        .collect();
-    let filtered_graph = petgraph::visit::EdgeFiltered::from_fn(&graph.primal, |e| {
-        let (source, target) = (e.source(), e.target());
-        !split.contains(&(source.min(target), source.max(target))) && *e.weight()
-    });
-    let mut dfs = Dfs::new(&filtered_graph, graph.primal.nodes().nth(1).unwrap());
-    let mut part = Vec::new();
-    while let Some(qubit) = dfs.next(&filtered_graph) {
-        part.push(node_map[&qubit]);
-    }
-    part
-}
-
 fn record_repr(record: &Record, graph: &SearchGraph) -> String {
     format!(
-        "Record {{ pattern: {}, split_part0: {:?}, cost: {:?} }}",
-        pattern_repr(&record.pattern, graph.num_slash()),
+        "Record {{ pattern: {}, split_part0: {:?}, cost: {:?}, score: {} }}",
+        record.pattern,
         split_part(&record.cutline.split, graph),
         &record.cost,
+        record.score,
     )
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
-    let config: Config;
-    if let Some(path) = cli.config {
-        config = Config::try_from_file(&path)?;
+    let mut config: Config;
+    if let Some(path) = &cli.config {
+        config = Config::try_from_file(path)?;
+    } else if let Some(preset) = cli.preset {
+        let preset = preset.config();
+        let mut topo = preset.topology;
+        topo.drop_islands = cli.drop_islands;
+        let mut algo = algorithm_config(&cli, topo.width.max(topo.height))?;
+        algo.ordering = preset.algorithm.ordering;
+        config = Config::new(topo, algo);
+    } else if let Some(mut topo) = file_topology(&cli)? {
+        topo.drop_islands = cli.drop_islands;
+        let algo = algorithm_config(&cli, topo.width.max(topo.height))?;
+        config = Config::new(topo, algo);
     } else {
+        let extent = match &cli.layout {
+            Some(path) => Some(Layout::from_file(path)?.extent()),
+            None if !cli.tiles.is_empty() => Some(tiles_extent(&cli.tiles)),
+            None => None,
+        };
         let width = cli
             .width
+            .or(extent.map(|e| e.0))
             .ok_or(anyhow! {"Width of the grid is not specified."})?;
         let height = cli
             .height
+            .or(extent.map(|e| e.1))
             .ok_or(anyhow! {"Height of the grid is not specified."})?;
-        let ordering = cli
-            .order
-            .chars()
-            .map(|c| Order::try_from(c).map_err(anyhow::Error::msg))
-            .collect::<Result<Vec<Order>>>()?;
         let topo = TopologyConfigBuilder::default()
             .width(width)
             .height(height)
-            .unused_qubits(cli.unused_qubits)
-            .unused_couplers(cli.unused_couplers)
+            .unused_qubits(cli.unused_qubits.clone())
+            .unused_couplers(cli.unused_couplers.clone())
+            .defects(cli.defects.clone())
+            .drop_islands(cli.drop_islands)
             .qubit_at_origin(cli.qubit_at_origin)
+            .lattice(cli.lattice)
+            .layout(cli.layout.clone())
+            .tiles(cli.tiles.clone())
+            .links(cli.links.clone())
             .build()?;
-        let algo = AlgorithmConfigBuilder::default()
-            .min_depth(cli.min_depth)
-            .max_depth(cli.max_depth.unwrap_or(width.max(height) as usize))
-            .max_unbalance(cli.max_unbalance)
-            .ordering(ordering)
-            .patterns(cli.patterns)
-            .max_patterns(cli.max_patterns)
-            .build()?;
+        let algo = algorithm_config(&cli, width.max(height))?;
         config = Config::new(topo, algo);
     }
 
+    // the calibration applies on top of the topology from any source
+    let calibration = match &cli.calibration {
+        Some(path) => {
+            let thresholds = Thresholds {
+                single_qubit: cli.max_single_qubit_error,
+                readout: cli.max_readout_error,
+                two_qubit: cli.max_two_qubit_error,
+            };
+            let (topology, summary) =
+                Calibration::from_file(path)?.apply(&config.topology, &thresholds);
+            config.topology = topology;
+            // shown before building the graph to explain connectivity errors
+            println!("- {}", summary);
+            Some(summary)
+        }
+        None => None,
+    };
+
     if let Some(path) = cli.save_config {
         config.save_to_json(&path)?;
     }
@@ -181,64 +405,290 @@ fn main() -> Result<()> {
         ));
         path
     };
+    // report topology and endpoints errors before creating the log file
+    let graph = SearchGraph::from_config(config.topology.clone())?;
+    if let Some(path) = &cli.save_mask {
+        std::fs::write(path, mask_repr(&graph)?)?;
+    }
+    if cli.save_dot.is_some() || cli.save_graph.is_some() {
+        let export = GraphExport::new(&graph);
+        if let Some(path) = &cli.save_dot {
+            std::fs::write(path, export.to_dot())?;
+        }
+        if let Some(path) = &cli.save_graph {
+            export.save_to_json(path)?;
+        }
+    }
+    let session = SearchSession::new(graph, config.algorithm.clone())?;
+    let graph = session.graph();
     let log_file = File::create(log_path)?;
     let mut result = BufWriter::new(log_file);
     writeln!(&mut result, "===config information===")?;
     serde_json::to_writer_pretty(&mut result, &config)?;
 
-    let topo = config.topology;
-    let graph = SearchGraph::from_config(topo)?;
-    let n_slash = graph.num_slash();
     let algo = config.algorithm;
-    let patterns = if let Some(patterns) = algo.patterns.clone() {
-        patterns
+    // bit patterns are laid out along the grid diagonals, heavy-hex patterns
+    // along its chains and square patterns along its rows and columns, a
+    // layout is searched with all its colorings
+    let patterns = if config.topology.layout.is_some() {
+        let patterns = if let Some(patterns) = &algo.patterns {
+            patterns
+                .iter()
+                .map(|p| coloring_from_repr(p, graph.edges.len()).map_err(anyhow::Error::msg))
+                .take(algo.max_patterns)
+                .collect::<Result<Vec<_>>>()?
+        } else {
+            search_vec_patterns(graph)
+                .take(algo.max_patterns)
+                .collect_vec()
+        };
+        Patterns::Coloring(patterns)
+    } else if config.topology.lattice == Lattice::HeavyHex {
+        let patterns = if let Some(patterns) = &algo.patterns {
+            patterns
+                .iter()
+                .map(|p| HexPattern::from_repr(p, graph.config.height).map_err(anyhow::Error::msg))
+                .take(algo.max_patterns)
+                .collect::<Result<Vec<_>>>()?
+        } else {
+            search_hex_patterns(graph)
+                .take(algo.max_patterns)
+                .collect_vec()
+        };
+        Patterns::Hex(patterns)
+    } else if config.topology.lattice == Lattice::Square {
+        let patterns = if let Some(patterns) = &algo.patterns {
+            patterns
+                .iter()
+                .map(|p| {
+                    SquarePattern::from_repr(p, graph.config.width, graph.config.height)
+                        .map_err(anyhow::Error::msg)
+                })
+                .take(algo.max_patterns)
+                .collect::<Result<Vec<_>>>()?
+        } else {
+            search_square_patterns(graph)
+                .take(algo.max_patterns)
+                .collect_vec()
+        };
+        Patterns::Square(patterns)
+    } else if !config.topology.tiles.is_empty() {
+        // the tiles share the bit patterns of the grid, the links take the
+        // orders left free at their qubits
+        let tiles = match algo.patterns.clone() {
+            Some(patterns) => patterns
+                .iter()
+                .map(|p| pattern_from_repr(p).map_err(anyhow::Error::msg))
+                .collect::<Result<Vec<_>>>()?,
+            None => search_bit_patterns(graph).collect_vec(),
+        };
+        let patterns = search_linked_patterns(graph, tiles)
+            .take(algo.max_patterns)
+            .collect_vec();
+        Patterns::Linked(patterns)
+    } else if let Some(patterns) = algo.patterns.clone() {
+        let patterns = patterns
             .into_iter()
-            .map(|ref p| pattern_from_repr(p))
+            .map(|ref p| pattern_from_repr(p).map_err(anyhow::Error::msg))
             .take(algo.max_patterns)
-            .collect_vec()
+            .collect::<Result<Vec<_>>>()?;
+        Patterns::Bit(patterns)
     } else {
-        search_bit_patterns(&graph)
+        let patterns = search_bit_patterns(graph)
             .take(algo.max_patterns)
-            .collect_vec()
+            .collect_vec();
+        Patterns::Bit(patterns)
     };
 
-    let cutlines = search_cutlines(&graph, &algo);
+    let n_cutlines = session.cutlines().len();
+    let n_patterns = patterns.len();
     writeln!(&mut result, "\n\n===search information===")?;
+    if let Some(summary) = &calibration {
+        writeln!(&mut result, "- {}", summary)?;
+    }
+    for island in &graph.dropped_islands {
+        print_and_log(
+            &mut result,
+            &format!(
+                "- Dropped an island of {} qubits: {:?}",
+                island.qubits.len(),
+                island.coordinates
+            ),
+        )?;
+    }
+    if !graph.config.tiles.is_empty() {
+        print_and_log(
+            &mut result,
+            &format!(
+                "- {} tiles joined by {} links",
+                graph.config.tiles.len(),
+                graph.links.len()
+            ),
+        )?;
+    }
     print_and_log(
         &mut result,
-        &format!("- Found {} valid cutlines", cutlines.len()),
+        &format!("- Found {} valid cutlines", n_cutlines),
     )?;
+    if let Some(endpoints) = &session.algorithm().endpoints {
+        print_and_log(
+            &mut result,
+            &format!("- Cutlines restricted to the endpoints {}", endpoints),
+        )?;
+    }
 
     print_and_log(
         &mut result,
-        &format!("- Search with {} patterns", patterns.len()),
+        &format!("- Search with {} patterns", n_patterns),
     )?;
 
+    // stop scheduling patterns on Ctrl-C and keep the results found so far
+    let cancel = CancelToken::new();
+    let handler_cancel = cancel.clone();
+    ctrlc::set_handler(move || handler_cancel.cancel())?;
+
+    // the search consumes the patterns
+    let kept_patterns = (cli.defect_sweep || cli.monte_carlo.is_some()).then(|| patterns.clone());
     let start_time = Instant::now();
-    let optimal_cutline = max_min_cost(&graph, patterns, cutlines, &algo);
+    let progress = TerminalProgress::new();
+    let search_result =
+        with_patterns!(patterns, patterns => session.evaluate(patterns, &progress, &cancel));
     let end_time = Instant::now();
     let elapsed_time = end_time - start_time;
     print_and_log(
         &mut result,
         &format!("- Total elapsed time: {:?}", elapsed_time),
     )?;
+    if search_result.partial {
+        print_and_log(
+            &mut result,
+            &format!(
+                "- Search stopped early, only {} patterns are evaluated",
+                search_result.n_evaluated
+            ),
+        )?;
+    }
+    if let Some(path) = cli.report {
+        SearchReport::new(
+            &search_result,
+            graph,
+            algo.cost_model,
+            n_cutlines,
+            n_patterns,
+        )
+        .save_to_json(&path)?;
+    }
+    let optimal_cutline = search_result.records;
     print_and_log(
         &mut result,
         &format!("- Found {} optimal cutlines", optimal_cutline.len()),
     )?;
 
-    writeln!(
-        &mut result,
-        "An example of optimal cutline:\n{}",
-        record_repr(&optimal_cutline[0], &graph)
-    )?;
+    if let Some(record) = optimal_cutline.first() {
+        writeln!(
+            &mut result,
+            "An example of optimal cutline:\n{}",
+            record_repr(record, graph)
+        )?;
+    }
+
+    if let Some(patterns) = kept_patterns.as_ref().filter(|_| cli.defect_sweep) {
+        let start_time = Instant::now();
+        let sweep = with_patterns!(patterns, patterns => defect_sweep(&session, patterns, &cancel));
+        print_and_log(
+            &mut result,
+            &format!(
+                "- Swept {} defects in {:?}, from the most harmful:",
+                sweep.entries.len(),
+                start_time.elapsed()
+            ),
+        )?;
+        for entry in &sweep.entries {
+            let line = match (entry.score, entry.ratio) {
+                (Some(score), Some(ratio)) => format!(
+                    "  {}: score {:.4e} (x{:.4}){}",
+                    entry.defect,
+                    score,
+                    ratio,
+                    if entry.pattern_changed {
+                        ", best patterns changed"
+                    } else {
+                        ""
+                    }
+                ),
+                _ => {
+                    // the first line of the error, without the list of components
+                    let error = entry.error.as_deref().unwrap_or_default();
+                    let error = error.lines().next().unwrap_or_default();
+                    format!("  {}: {}", entry.defect, error.trim_end_matches(':'))
+                }
+            };
+            print_and_log(&mut result, &line)?;
+        }
+        if sweep.partial {
+            print_and_log(&mut result, "- Sweep stopped early")?;
+        }
+        if let Some(path) = &cli.sweep_report {
+            sweep.save_to_json(path)?;
+        }
+    }
+
+    if let (Some(patterns), Some(n_samples)) = (&kept_patterns, cli.monte_carlo) {
+        let mc_config = MonteCarloConfig {
+            n_samples,
+            seed: cli.seed,
+            qubit_failure: cli.qubit_failure,
+            coupler_failure: cli.coupler_failure,
+            disconnected: cli.disconnected,
+        };
+        let start_time = Instant::now();
+        let graph = session.graph();
+        let report = with_patterns!(patterns, patterns => {
+            monte_carlo(graph, patterns, &algo, &mc_config, &cancel)
+        });
+        print_and_log(
+            &mut result,
+            &format!(
+                "- Drew {} random defect sets with seed {} in {:?}, {} discarded",
+                report.samples.len(),
+                mc_config.seed,
+                start_time.elapsed(),
+                report.n_discarded
+            ),
+        )?;
+        if let Some(scores) = &report.scores {
+            print_and_log(
+                &mut result,
+                &format!(
+                    "  optimal score: min {:.4e}, p10 {:.4e}, median {:.4e}, p90 {:.4e}, max {:.4e}",
+                    scores.min, scores.p10, scores.median, scores.p90, scores.max
+                ),
+            )?;
+        }
+        let n_partial = report.samples.iter().filter(|s| s.partial).count();
+        if n_partial > 0 {
+            print_and_log(
+                &mut result,
+                &format!("  {} samples ran out of the time budget", n_partial),
+            )?;
+        }
+        for (pattern, wins) in report.pattern_wins.iter().take(10) {
+            print_and_log(&mut result, &format!("  {} wins {} samples", pattern, wins))?;
+        }
+        if report.partial {
+            print_and_log(&mut result, "- Random defect sets stopped early")?;
+        }
+        if let Some(path) = &cli.monte_carlo_report {
+            report.save_to_json(path)?;
+        }
+    }
 
     writeln!(
         &mut result,
         "\n===patterns own optimal cutlines===\n{:#?}",
         optimal_cutline
             .into_iter()
-            .map(|r| pattern_repr(&r.pattern, n_slash))
+            .map(|r| r.pattern.to_string())
             .collect_vec()
     )?;
 
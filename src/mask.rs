@@ -0,0 +1,248 @@
+use crate::config::{TopologyConfig, TopologyConfigBuilder};
+use crate::graph::{grid_qubits, in_primal, GraphError, Lattice, SearchGraph};
+use crate::input::{InputError, InputFile};
+use itertools::Itertools;
+use std::path::Path;
+
+/// A live qubit
+pub const LIVE_QUBIT: char = 'O';
+/// A dead qubit, the couplers touching it are dead as well
+pub const DEAD_QUBIT: char = 'X';
+/// A grid point without qubit
+pub const NO_QUBIT: char = '.';
+/// A dead coupler between two live qubits
+pub const DEAD_COUPLER: char = 'x';
+
+/// Parse an ASCII-art chip mask of a rotated grid.
+///
+/// The grid point `(x, y)` is drawn at column `2x` of line `2y`, and the
+/// coupler crossing the square between `(x, y)` and `(x + 1, y + 1)` at
+/// column `2x + 1` of line `2y + 1`. A live coupler is drawn as `/` or `\`
+/// following its direction, or left blank. A 4x3 grid without qubit at the
+/// origin, a dead qubit at `(3, 0)` and a dead coupler between `(1, 2)` and
+/// `(2, 1)` reads:
+///
+/// ```text
+/// . O . X
+///  / \ /
+/// O . O .
+///  \ x \
+/// . O . O
+/// ```
+///
+/// The qubit glyph at the origin sets `qubit_at_origin`, the other qubits
+/// must follow the parity of the rotated grid. Lines starting with `#` are
+/// comments.
+pub fn topology_from_mask(mask: &str) -> Result<TopologyConfig, GraphError> {
+    let invalid = |message: String| GraphError::InvalidMask { message };
+    let lines = mask
+        .lines()
+        .filter(|l| !l.starts_with('#'))
+        .map(|l| l.trim_end().chars().collect_vec())
+        .collect_vec();
+    let n_lines = lines
+        .iter()
+        .rposition(|l| !l.is_empty())
+        .map_or(0, |i| i + 1);
+    let n_columns = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+    if n_lines % 2 == 0 || n_columns % 2 == 0 {
+        return Err(invalid(format!(
+            "the mask has {} lines and {} columns, both should be odd",
+            n_lines, n_columns
+        )));
+    }
+    let glyph = |column: usize, line: usize| lines[line].get(column).copied().unwrap_or(' ');
+    let width = (n_columns as u32).div_ceil(2);
+    let height = (n_lines as u32).div_ceil(2);
+    let qubit_at_origin = match glyph(0, 0) {
+        LIVE_QUBIT | DEAD_QUBIT => true,
+        NO_QUBIT => false,
+        c => return Err(invalid(format!("unknown glyph '{}' at the origin", c))),
+    };
+    let qubits_map = grid_qubits(width, height, qubit_at_origin);
+
+    let mut unused_qubits = Vec::new();
+    let mut unused_couplers = Vec::new();
+    for (line, column) in (0..n_lines).cartesian_product(0..n_columns) {
+        let c = glyph(column, line);
+        let (x, y) = ((column / 2) as i32, (line / 2) as i32);
+        let expected = match (column % 2, line % 2) {
+            (0, 0) if in_primal(x, y, qubit_at_origin) => {
+                if c == DEAD_QUBIT {
+                    unused_qubits.push(qubits_map[&(x, y)]);
+                }
+                [LIVE_QUBIT, DEAD_QUBIT]
+            }
+            (0, 0) => [NO_QUBIT; 2],
+            (1, 1) => {
+                // the coupler of the square joins the two corners holding qubits
+                let (n1, n2, slash) = if in_primal(x, y, qubit_at_origin) {
+                    ((x, y), (x + 1, y + 1), '\\')
+                } else {
+                    ((x + 1, y), (x, y + 1), '/')
+                };
+                if c == DEAD_COUPLER {
+                    let (i1, i2) = (qubits_map[&n1], qubits_map[&n2]);
+                    unused_couplers.push((i1.min(i2), i1.max(i2)));
+                }
+                [slash, DEAD_COUPLER]
+            }
+            _ => [' '; 2],
+        };
+        // a blank coupler is live
+        let blank_coupler = line % 2 == 1 && c == ' ';
+        if !expected.contains(&c) && !blank_coupler {
+            return Err(invalid(format!(
+                "unexpected glyph '{}' at line {} column {}, expected '{}'",
+                c,
+                line + 1,
+                column + 1,
+                expected.iter().unique().join("' or '")
+            )));
+        }
+    }
+    Ok(TopologyConfigBuilder::default()
+        .width(width)
+        .height(height)
+        .qubit_at_origin(qubit_at_origin)
+        .unused_qubits(unused_qubits)
+        .unused_couplers(unused_couplers)
+        .build()
+        .unwrap())
+}
+
+/// Load a chip mask from a text file, see [`topology_from_mask`]. An
+/// unreadable file and a malformed mask both fail with an [`InputError`].
+pub fn topology_from_mask_file(path: &Path) -> Result<TopologyConfig, InputError> {
+    let mask_error = |message: String| InputError::new(InputFile::Mask, path, message);
+    let mask = std::fs::read_to_string(path).map_err(|e| mask_error(e.to_string()))?;
+    topology_from_mask(&mask).map_err(|e| match e {
+        GraphError::InvalidMask { message } => mask_error(message),
+        e => mask_error(e.to_string()),
+    })
+}
+
+/// Draw the rotated grid of the graph as a chip mask, see
+/// [`topology_from_mask`]. The couplers touching a dead qubit are drawn as
+/// live, as they are implied by the qubit.
+pub fn mask_repr(graph: &SearchGraph) -> Result<String, GraphError> {
+    let config = &graph.config;
+    if config.layout.is_some() || config.lattice != Lattice::Rotated {
+        return Err(GraphError::InvalidMask {
+            message: "only rotated grids can be drawn as a mask".to_string(),
+        });
+    }
+    if !config.tiles.is_empty() {
+        return Err(GraphError::InvalidMask {
+            message: "the links between tiles cannot be drawn as a mask".to_string(),
+        });
+    }
+    let (width, height) = (config.width as i32, config.height as i32);
+    let qubit_at_origin = config.qubit_at_origin;
+    let mut lines = Vec::new();
+    for y in 0..height {
+        let points = (0..width).map(|x| {
+            if !in_primal(x, y, qubit_at_origin) {
+                NO_QUBIT
+            } else if graph.unused_qubits.contains(&(x, y)) {
+                DEAD_QUBIT
+            } else {
+                LIVE_QUBIT
+            }
+        });
+        lines.push(points.map(String::from).join(" "));
+        if y == height - 1 {
+            break;
+        }
+        let couplers = (0..width - 1).map(|x| {
+            let (n1, n2, slash) = if in_primal(x, y, qubit_at_origin) {
+                ((x, y), (x + 1, y + 1), '\\')
+            } else {
+                ((x + 1, y), (x, y + 1), '/')
+            };
+            let dead_qubit = graph.unused_qubits.contains(&n1) || graph.unused_qubits.contains(&n2);
+            match graph.primal.edge_weight(n1, n2) {
+                Some(false) if !dead_qubit => DEAD_COUPLER,
+                _ => slash,
+            }
+        });
+        lines.push(format!(" {}", couplers.map(String::from).join(" ")));
+    }
+    Ok(lines.join("\n") + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mask() {
+        let mask = "# a 4x3 chip\n. O . X\n / \\ /\nO . O .\n \\ x \\\n. O . O\n";
+        let topo = topology_from_mask(mask).unwrap();
+        assert_eq!((topo.width, topo.height), (4, 3));
+        assert!(!topo.qubit_at_origin);
+        assert_eq!(topo.unused_qubits, vec![1]);
+        assert_eq!(topo.unused_couplers, vec![(3, 4)]);
+        let graph = SearchGraph::from_config(topo).unwrap();
+        assert_eq!(
+            mask_repr(&graph).unwrap(),
+            mask.replacen("# a 4x3 chip\n", "", 1)
+        );
+
+        // live couplers may be left blank
+        let blank = ". O . X\n\nO . O .\n   x\n. O . O";
+        assert_eq!(topology_from_mask(blank).unwrap(), graph.config);
+
+        for invalid in [
+            ". O . X\n / \\ /\n",
+            "O O . X\n / \\ /\nO . O .\n \\ x \\\n. O . O\n",
+            ". O . X\n / / /\nO . O .\n \\ x \\\n. O . O\n",
+        ] {
+            assert!(matches!(
+                topology_from_mask(invalid),
+                Err(GraphError::InvalidMask { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_mask_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chip.txt");
+        std::fs::write(&path, ". O . X\n / \\ /\nO . O .\n \\ x \\\n. O . O\n").unwrap();
+        let topo = topology_from_mask_file(&path).unwrap();
+        assert_eq!(topo.unused_couplers, vec![(3, 4)]);
+
+        std::fs::write(&path, ". O . X\n / \\ /\n").unwrap();
+        let error = topology_from_mask_file(&path).unwrap_err();
+        assert_eq!(error.file, InputFile::Mask);
+        assert!(error.message.contains("both should be odd"));
+
+        let missing = dir.path().join("missing.txt");
+        assert!(matches!(
+            topology_from_mask_file(&missing),
+            Err(InputError {
+                file: InputFile::Mask,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_mask_round_trip() {
+        let mut config = TopologyConfig::default();
+        config.unused_qubits.extend([5, 40]);
+        config.unused_couplers.extend([(0, 6), (11, 5), (23, 28)]);
+        let graph = SearchGraph::from_config(config).unwrap();
+        let topo = topology_from_mask(&mask_repr(&graph).unwrap()).unwrap();
+        assert_eq!(topo.unused_qubits, vec![5, 40]);
+        // the coupler touching the dead qubit is implied by it
+        assert_eq!(topo.unused_couplers, vec![(0, 6), (23, 28)]);
+        let round_trip = SearchGraph::from_config(topo).unwrap();
+        assert_eq!(round_trip.unused_qubits, graph.unused_qubits);
+        assert!(graph
+            .primal
+            .all_edges()
+            .all(|(n1, n2, used)| round_trip.primal.edge_weight(n1, n2) == Some(used)));
+    }
+}
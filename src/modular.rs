@@ -0,0 +1,527 @@
+//! Modular topologies, rectangular tiles of the rotated grid joined by
+//! long-range links.
+//!
+//! The [`Tile`]s of the topology config are cut from its grid, the qubits
+//! out of every tile are left out and only the couplers within a tile are
+//! kept. The links of the config couple qubits of two different tiles by
+//! their indices, which follow the row-major order of the qubits of all the
+//! tiles on the grid.
+//!
+//! The links make the coupling graph non-planar, so they have no dual edge.
+//! The dual graph is the union of the duals of the tiles, and the search
+//! finds the cuts within a tile as usual.
+//! [`search_cutlines`](crate::cutline::search_cutlines) then treats each link
+//! as a cut candidate on top of them: the used qubits are split into blocks
+//! by the cut of at most one tile and every used link, and each way of
+//! putting the blocks on two connected sides gives a cutline made of the cut
+//! of the tile and the links between the sides. The cutlines cutting no tile
+//! only cross links.
+//!
+//! The first block stays on the first side and each of the `n` blocks is put
+//! on either side, so the `2^(n-1)` ways are enumerated for every split. The
+//! search fails with [`CutlineError::TooManyBlocks`] when a split leaves
+//! more than 16 blocks, e.g. on a topology of more than 15 tiles, rather
+//! than missing cutlines.
+use crate::cutline::{Cutline, CutlineError, Edge};
+use crate::graph::{GraphError, Point, SearchGraph};
+use indexmap::IndexMap;
+use itertools::Itertools;
+use petgraph::unionfind::UnionFind;
+use petgraph::visit::{Dfs, EdgeFiltered, EdgeRef};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+/// The most blocks of used qubits whose sides are enumerated, see the
+/// [module documentation](self).
+pub const MAX_BLOCKS: usize = 16;
+
+/// A rectangle of the rotated grid, from the column `x` and the row `y`.
+///
+/// The text form is `WxH@X,Y`, e.g. `6x6@6,0` for the 6 x 6 tile starting
+/// at the column 6 of the first row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Tile {
+    pub fn contains(&self, p: Point) -> bool {
+        let (x, y) = (self.x as i32, self.y as i32);
+        (x..x + self.width as i32).contains(&p.0) && (y..y + self.height as i32).contains(&p.1)
+    }
+
+    /// Whether the point lies on the border of the tile.
+    pub fn on_border(&self, p: Point) -> bool {
+        let (x, y) = (self.x as i32, self.y as i32);
+        let (right, bottom) = (x + self.width as i32 - 1, y + self.height as i32 - 1);
+        self.contains(p) && (p.0 == x || p.0 == right || p.1 == y || p.1 == bottom)
+    }
+
+    fn overlaps(&self, other: &Tile) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+}
+
+impl fmt::Display for Tile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}@{},{}", self.width, self.height, self.x, self.y)
+    }
+}
+
+impl FromStr for Tile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid tile '{}', expected 'WxH@X,Y'", s);
+        let (size, origin) = s.trim().split_once('@').ok_or_else(invalid)?;
+        let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+        let (x, y) = origin.split_once(',').ok_or_else(invalid)?;
+        let parse = |v: &str| v.trim().parse::<u32>().map_err(|_| invalid());
+        Ok(Tile {
+            x: parse(x)?,
+            y: parse(y)?,
+            width: parse(width)?,
+            height: parse(height)?,
+        })
+    }
+}
+
+/// The width and height of the smallest grid holding all the tiles.
+pub fn tiles_extent(tiles: &[Tile]) -> (u32, u32) {
+    let width = tiles.iter().map(|t| t.x + t.width).max().unwrap_or(0);
+    let height = tiles.iter().map(|t| t.y + t.height).max().unwrap_or(0);
+    (width, height)
+}
+
+/// The index of the tile holding the point.
+pub(crate) fn tile_of(tiles: &[Tile], p: Point) -> Option<usize> {
+    tiles.iter().position(|t| t.contains(p))
+}
+
+/// Verify the tiles lie on the grid without overlapping each other, and
+/// keep the qubits of the grid within the tiles, indexed again in row-major
+/// order.
+pub(crate) fn tile_qubits(
+    tiles: &[Tile],
+    grid_qubits: IndexMap<Point, u32>,
+    grid: (u32, u32),
+) -> Result<IndexMap<Point, u32>, GraphError> {
+    let invalid = |message: String| Err(GraphError::InvalidTiles { message });
+    for tile in tiles {
+        if tile.width < 2 || tile.height < 2 {
+            return invalid(format!("the tile {} is degenerate", tile));
+        }
+        if tile.x + tile.width > grid.0 || tile.y + tile.height > grid.1 {
+            return invalid(format!(
+                "the tile {} exceeds the {}x{} grid",
+                tile, grid.0, grid.1
+            ));
+        }
+    }
+    if let Some((t1, t2)) = tiles
+        .iter()
+        .tuple_combinations()
+        .find(|(t1, t2)| t1.overlaps(t2))
+    {
+        return invalid(format!("the tiles {} and {} overlap", t1, t2));
+    }
+    Ok(grid_qubits
+        .into_keys()
+        .filter(|&p| tile_of(tiles, p).is_some())
+        .enumerate()
+        .map(|(i, p)| (p, i as u32))
+        .collect())
+}
+
+/// The links of the config by the coordinates of their qubits, each of them
+/// joining two different tiles.
+pub(crate) fn resolve_links(
+    tiles: &[Tile],
+    qubits_map: &IndexMap<Point, u32>,
+    links: &[(u32, u32)],
+) -> Result<Vec<Edge>, GraphError> {
+    let qubit = |i: u32| qubits_map.get_index(i as usize).map(|(&p, _)| p);
+    let mut edges = Vec::new();
+    let mut invalid = Vec::new();
+    for &(i1, i2) in links {
+        match (qubit(i1), qubit(i2)) {
+            (Some(p1), Some(p2)) if tile_of(tiles, p1) != tile_of(tiles, p2) => {
+                edges.push((p1.min(p2), p1.max(p2)));
+            }
+            _ => invalid.push((i1, i2)),
+        }
+    }
+    if !invalid.is_empty() {
+        return Err(GraphError::InvalidLinks { links: invalid });
+    }
+    Ok(edges.into_iter().unique().collect())
+}
+
+/// The cutlines of a graph with links, from the splits found within its
+/// tiles, see the [module documentation](self).
+///
+/// The split of a cutline lists the couplers cut within the tile, then the
+/// used links between the two sides. The splits crossing no used coupler of
+/// a tile are the same as cutting no tile at all.
+pub(crate) fn link_cutlines(
+    graph: &SearchGraph,
+    splits: Vec<Vec<Edge>>,
+    max_unbalance: usize,
+) -> Result<Vec<Cutline>, CutlineError> {
+    let used = |e: &Edge| *graph.primal.edge_weight(e.0, e.1).unwrap();
+    let links = graph
+        .links
+        .iter()
+        .filter(|l| used(l))
+        .copied()
+        .collect_vec();
+    let mut cutlines = Vec::new();
+    for split in std::iter::once(Vec::new()).chain(splits) {
+        cutlines.extend(side_cutlines(graph, &links, split)?);
+    }
+    Ok(cutlines
+        .into_iter()
+        .filter(|cutline| cutline.unbalance <= max_unbalance)
+        .unique_by(|cutline| {
+            let mut key = cutline.split.iter().filter(|e| used(e)).collect_vec();
+            key.sort();
+            key.into_iter().copied().collect_vec()
+        })
+        .collect())
+}
+
+/// Every way of putting the blocks left by the split and the used links on
+/// two connected sides, such that each used coupler of the split runs
+/// between the sides.
+fn side_cutlines(
+    graph: &SearchGraph,
+    links: &[Edge],
+    split: Vec<Edge>,
+) -> Result<Vec<Cutline>, CutlineError> {
+    let (block_of, sizes) = blocks(graph, split.iter().chain(links));
+    let n_blocks = sizes.len();
+    if n_blocks > MAX_BLOCKS {
+        return Err(CutlineError::TooManyBlocks {
+            n_blocks,
+            max_blocks: MAX_BLOCKS,
+        });
+    }
+    if n_blocks < 2 {
+        return Ok(Vec::new());
+    }
+    let block_pair = |&(n1, n2): &Edge| (block_of[&n1], block_of[&n2]);
+    let split_pairs = split
+        .iter()
+        .filter(|e| *graph.primal.edge_weight(e.0, e.1).unwrap())
+        .map(block_pair)
+        .collect_vec();
+    let link_pairs = links.iter().map(block_pair).collect_vec();
+    // the first block is always on the first side
+    let cutlines = (1..1usize << (n_blocks - 1))
+        .filter_map(|mask| {
+            let side = |b: usize| ((mask << 1) >> b) & 1 == 1;
+            if split_pairs.iter().any(|&(b1, b2)| side(b1) == side(b2)) {
+                return None;
+            }
+            let mut sides = UnionFind::new(n_blocks);
+            for &(b1, b2) in &link_pairs {
+                if side(b1) == side(b2) {
+                    sides.union(b1, b2);
+                }
+            }
+            if (0..n_blocks).map(|b| sides.find(b)).unique().count() != 2 {
+                return None;
+            }
+            let size: usize = (0..n_blocks).filter(|&b| side(b)).map(|b| sizes[b]).sum();
+            let other = sizes.iter().sum::<usize>() - size;
+            let cut_links = links
+                .iter()
+                .zip(&link_pairs)
+                .filter(|(_, &(b1, b2))| side(b1) != side(b2))
+                .map(|(&link, _)| link);
+            Some(Cutline {
+                split: split.iter().copied().chain(cut_links).collect(),
+                unbalance: size.abs_diff(other),
+            })
+        })
+        .collect();
+    Ok(cutlines)
+}
+
+/// The unbalance of the split if it is a cutline of the graph with links,
+/// which leaves exactly two connected sides with each of its used couplers
+/// running between them.
+pub(crate) fn link_cut_unbalance(graph: &SearchGraph, split: &[Edge]) -> Option<usize> {
+    let (side_of, sizes) = blocks(graph, split);
+    let crossing = split
+        .iter()
+        .filter(|e| *graph.primal.edge_weight(e.0, e.1).unwrap())
+        .all(|(n1, n2)| side_of[n1] != side_of[n2]);
+    (sizes.len() == 2 && crossing).then(|| sizes[0].abs_diff(sizes[1]))
+}
+
+/// The connected components of the used qubits without the given couplers,
+/// by the component of each qubit and the size of each component.
+fn blocks<'a>(
+    graph: &SearchGraph,
+    removed: impl IntoIterator<Item = &'a Edge>,
+) -> (HashMap<Point, usize>, Vec<usize>) {
+    let removed: HashSet<&Edge> = removed.into_iter().collect();
+    let filtered = EdgeFiltered::from_fn(&graph.primal, |e| {
+        let (source, target) = (e.source(), e.target());
+        *e.weight() && !removed.contains(&(source.min(target), source.max(target)))
+    });
+    let mut block_of = HashMap::new();
+    let mut sizes = Vec::new();
+    for qubit in graph.primal.nodes() {
+        if graph.unused_qubits.contains(&qubit) || block_of.contains_key(&qubit) {
+            continue;
+        }
+        let mut dfs = Dfs::new(&filtered, qubit);
+        let mut size = 0;
+        while let Some(n) = dfs.next(&filtered) {
+            block_of.insert(n, sizes.len());
+            size += 1;
+        }
+        sizes.push(size);
+    }
+    (block_of, sizes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cancel::CancelToken;
+    use crate::config::{AlgorithmConfigBuilder, TopologyConfig, TopologyConfigBuilder};
+    use crate::cost::max_min_cost;
+    use crate::cutline::search_cutlines;
+    use crate::graph::{grid_qubits, Lattice};
+    use crate::progress::SilentProgress;
+    use crate::search_pattern::{search_bit_patterns, search_linked_patterns};
+
+    /// Two 6 x 6 tiles side by side, joined by three crossing links.
+    fn two_tiles() -> TopologyConfig {
+        TopologyConfigBuilder::default()
+            .width(12)
+            .height(6)
+            .tiles(vec!["6x6@0,0".parse().unwrap(), "6x6@6,0".parse().unwrap()])
+            .links(vec![(2, 33), (14, 21), (26, 9)])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_modular_graph() {
+        let graph = SearchGraph::from_config(two_tiles()).unwrap();
+        assert_eq!(graph.primal.node_count(), 36);
+        // 25 couplers in each tile
+        assert_eq!(graph.primal.edge_count(), 2 * 25 + 3);
+        assert_eq!(graph.links[0], ((5, 0), (6, 5)));
+        assert!(graph.is_link((6, 3), (5, 2)));
+        assert!(!graph.primal.contains_edge((5, 0), (6, 1)));
+        assert_eq!(graph.crossings.len(), 2 * 25);
+        assert!(graph.dual_boundaries.iter().any(|n| n.0 == 5));
+        assert!(graph.dual_boundaries.iter().any(|n| n.0 == 6));
+        // the tiles keep the diagonals of the whole grid
+        let grid = SearchGraph::from_config(
+            TopologyConfigBuilder::default()
+                .width(12)
+                .height(6)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(graph.num_slash(), grid.num_slash());
+        assert_eq!(graph.num_back_slash(), grid.num_back_slash());
+
+        // without the links the tiles are disconnected
+        let mut config = two_tiles();
+        config.links.clear();
+        assert!(matches!(
+            SearchGraph::from_config(config),
+            Err(GraphError::Disconnected { components }) if components.len() == 2
+        ));
+        // the links are couplers of the config
+        let mut config = two_tiles();
+        config.unused_couplers.push((21, 14));
+        let graph = SearchGraph::from_config(config).unwrap();
+        assert_eq!(graph.primal.edge_weight((5, 2), (6, 3)), Some(&false));
+    }
+
+    #[test]
+    fn test_invalid_tiles() {
+        let invalid = |tiles: &[&str], links: Vec<(u32, u32)>| {
+            let mut config = two_tiles();
+            config.tiles = tiles.iter().map(|t| t.parse().unwrap()).collect();
+            config.links = links;
+            SearchGraph::from_config(config).unwrap_err()
+        };
+        let overlap = invalid(&["6x6@0,0", "6x6@5,0"], vec![]);
+        assert_eq!(
+            overlap.to_string(),
+            "Invalid tiles: the tiles 6x6@0,0 and 6x6@5,0 overlap"
+        );
+        let out = invalid(&["6x6@0,0", "6x6@7,0"], vec![]);
+        assert!(matches!(out, GraphError::InvalidTiles { .. }));
+        assert!(matches!(
+            invalid(&["6x1@0,0", "6x5@0,1"], vec![]),
+            GraphError::InvalidTiles { .. }
+        ));
+        // a link within a tile and a link out of range
+        assert_eq!(
+            invalid(&["6x6@0,0", "6x6@6,0"], vec![(0, 1), (2, 33), (3, 36)]),
+            GraphError::InvalidLinks {
+                links: vec![(0, 1), (3, 36)]
+            }
+        );
+        let mut config = two_tiles();
+        config.lattice = Lattice::HeavyHex;
+        assert!(matches!(
+            SearchGraph::from_config(config),
+            Err(GraphError::InvalidTiles { .. })
+        ));
+
+        let tile: Tile = " 4x3@2,1".parse().unwrap();
+        assert_eq!((tile.x, tile.y, tile.width, tile.height), (2, 1, 4, 3));
+        assert_eq!(tile.to_string(), "4x3@2,1");
+        assert!(tile.on_border((2, 2)) && !tile.on_border((3, 2)));
+        assert!("4x3".parse::<Tile>().is_err());
+        assert_eq!(tiles_extent(&two_tiles().tiles), (12, 6));
+    }
+
+    #[test]
+    fn test_link_cutlines() {
+        let graph = SearchGraph::from_config(two_tiles()).unwrap();
+        let algo = AlgorithmConfigBuilder::default()
+            .max_depth(6)
+            .max_unbalance(6)
+            .build()
+            .unwrap();
+        let cutlines = search_cutlines(&graph, &algo).unwrap();
+        // the tiles are split apart by cutting all the links
+        let links = graph.links.clone();
+        assert!(cutlines.contains(&Cutline {
+            split: links.clone(),
+            unbalance: 0
+        }));
+        assert!(cutlines.iter().all(|c| c.unbalance <= 6));
+        for cutline in &cutlines {
+            assert_eq!(
+                link_cut_unbalance(&graph, &cutline.split),
+                Some(cutline.unbalance),
+                "{:?}",
+                cutline.split
+            );
+        }
+        // a cut of a tile crosses the links from its far side
+        let within_tile = cutlines
+            .iter()
+            .filter(|c| c.split.iter().any(|e| !graph.is_link(e.0, e.1)))
+            .collect_vec();
+        assert!(!within_tile.is_empty());
+        assert!(within_tile
+            .iter()
+            .any(|c| c.split.iter().any(|e| graph.is_link(e.0, e.1))));
+        // a single link never separates the tiles
+        assert!(link_cut_unbalance(&graph, &links[..1]).is_none());
+        let keys = cutlines.iter().map(|c| c.canonical_key()).collect_vec();
+        assert_eq!(keys.iter().unique().count(), keys.len());
+    }
+
+    #[test]
+    fn test_interior_link() {
+        // a link between interior qubits, no plain pattern orders it
+        let mut config = two_tiles();
+        config.links = vec![(13, 16)];
+        let graph = SearchGraph::from_config(config).unwrap();
+        let algo = AlgorithmConfigBuilder::default()
+            .max_depth(6)
+            .build()
+            .unwrap();
+        let cutlines = search_cutlines(&graph, &algo).unwrap();
+        let link = graph.edge_index(graph.links[0].0, graph.links[0].1);
+        let wrapped = cutlines
+            .iter()
+            .map(|c| c.clone().into_wrapped(&graph))
+            .collect_vec();
+        assert!(wrapped.iter().any(|c| c.split.contains(&link)));
+        assert!(wrapped.iter().all(|c| c
+            .wedge_candidates
+            .iter()
+            .chain(&c.dcd_candidates)
+            .all(|&(e1, e2)| e1 != link && e2 != link)));
+
+        let patterns = search_bit_patterns(&graph).take(50).collect_vec();
+        let cancel = CancelToken::new();
+        let plain = max_min_cost(
+            &graph,
+            patterns.clone(),
+            cutlines.clone(),
+            &algo,
+            &SilentProgress,
+            &cancel,
+        );
+        assert!(!plain.records.is_empty());
+        let linked = search_linked_patterns(&graph, patterns).collect_vec();
+        let linked = max_min_cost(&graph, linked, cutlines, &algo, &SilentProgress, &cancel);
+        assert!(!linked.records.is_empty());
+    }
+
+    #[test]
+    fn test_too_many_blocks() {
+        // a row of 3 x 3 tiles, each linked to the next one
+        let tiles_config = |n_tiles: u32| {
+            let qubits = grid_qubits(3 * n_tiles, 3, false);
+            let column = |x: u32| {
+                qubits
+                    .iter()
+                    .filter(move |(p, _)| p.0 == x as i32)
+                    .map(|(_, &q)| q)
+            };
+            let links = (1..n_tiles)
+                .map(|i| {
+                    (
+                        column(3 * i - 1).next().unwrap(),
+                        column(3 * i).next_back().unwrap(),
+                    )
+                })
+                .collect_vec();
+            TopologyConfigBuilder::default()
+                .width(3 * n_tiles)
+                .height(3)
+                .tiles(
+                    (0..n_tiles)
+                        .map(|i| format!("3x3@{},0", 3 * i).parse().unwrap())
+                        .collect(),
+                )
+                .links(links)
+                .build()
+                .unwrap()
+        };
+        let algo = AlgorithmConfigBuilder::default()
+            .max_depth(3)
+            .max_unbalance(100)
+            .build()
+            .unwrap();
+        let graph = SearchGraph::from_config(tiles_config(MAX_BLOCKS as u32 - 1)).unwrap();
+        assert!(!search_cutlines(&graph, &algo).unwrap().is_empty());
+        // a cut of one of the tiles leaves one more block than can be
+        // enumerated
+        let graph = SearchGraph::from_config(tiles_config(MAX_BLOCKS as u32)).unwrap();
+        assert_eq!(
+            search_cutlines(&graph, &algo),
+            Err(CutlineError::TooManyBlocks {
+                n_blocks: MAX_BLOCKS + 1,
+                max_blocks: MAX_BLOCKS
+            })
+        );
+    }
+}
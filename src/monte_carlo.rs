@@ -0,0 +1,359 @@
+//! Distribution of the optimal cost over random defects.
+//!
+//! [`monte_carlo`] draws random defect sets on the topology of a graph,
+//! every live qubit and live coupler failing independently with the given
+//! probability, and runs [`search_cutlines`] and
+//! [`max_min_cost`](crate::cost::max_min_cost) on each sample. The samples
+//! are drawn from seeds derived from the seed of the [`MonteCarloConfig`], so
+//! that the whole run and every single sample can be reproduced.
+use crate::cancel::CancelToken;
+use crate::config::AlgorithmConfig;
+use crate::cost::max_min_cost_wrapped;
+use crate::cutline::search_cutlines;
+use crate::defect::Defect;
+use crate::graph::{GraphError, SearchGraph};
+use crate::pattern::{Pattern, PatternDescription};
+use crate::progress::SilentProgress;
+use anyhow::Result;
+use clap::ValueEnum;
+use itertools::Itertools;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+
+/// How to handle a sample whose live qubits are not connected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Disconnected {
+    /// Discard the sample
+    #[default]
+    Discard,
+    /// Keep the largest connected component, see `drop_islands`
+    DropIslands,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MonteCarloConfig {
+    pub n_samples: usize,
+    pub seed: u64,
+    /// Failure probability of each live qubit
+    pub qubit_failure: f64,
+    /// Failure probability of each live coupler
+    pub coupler_failure: f64,
+    pub disconnected: Disconnected,
+}
+
+/// A random defect set and the optimal cost of the topology with it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonteCarloSample {
+    /// Seed drawing the defects of this sample
+    pub seed: u64,
+    pub defects: Vec<Defect>,
+    /// The optimal score, `None` if the sample is discarded
+    pub score: Option<f64>,
+    pub best_patterns: Vec<PatternDescription>,
+    pub n_cutlines: usize,
+    /// Number of qubits dropped with the islands
+    pub n_dropped: usize,
+    /// Whether the time budget ran out before all the patterns were
+    /// evaluated, the score is then the best of the evaluated patterns
+    #[serde(default)]
+    pub partial: bool,
+    /// Why the sample is discarded
+    pub error: Option<String>,
+}
+
+/// Nearest-rank quantiles of the optimal scores of the kept samples.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScoreDistribution {
+    pub min: f64,
+    pub p10: f64,
+    pub median: f64,
+    pub p90: f64,
+    pub max: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonteCarloReport {
+    pub config: MonteCarloConfig,
+    pub samples: Vec<MonteCarloSample>,
+    pub n_discarded: usize,
+    /// `None` if every sample is discarded
+    pub scores: Option<ScoreDistribution>,
+    /// The number of samples won by each pattern, in descending order
+    pub pattern_wins: Vec<(PatternDescription, usize)>,
+    /// Whether the run was cancelled before all the samples were drawn
+    pub partial: bool,
+}
+
+impl MonteCarloReport {
+    pub fn save_to_json(&self, path: &Path) -> Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+impl ScoreDistribution {
+    fn new(scores: &[f64]) -> Option<Self> {
+        let sorted = scores
+            .iter()
+            .copied()
+            .sorted_by(|s1, s2| s1.partial_cmp(s2).unwrap())
+            .collect_vec();
+        let quantile = |q: f64| {
+            let rank = (q * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+        (!sorted.is_empty()).then(|| Self {
+            min: sorted[0],
+            p10: quantile(0.1),
+            median: quantile(0.5),
+            p90: quantile(0.9),
+            max: sorted[sorted.len() - 1],
+        })
+    }
+}
+
+/// Draw the defects of a sample, each live qubit and then each live coupler
+/// in the order of the primal graph failing with its probability. The
+/// couplers touching a failed qubit are left out.
+pub fn draw_defects(graph: &SearchGraph, config: &MonteCarloConfig, seed: u64) -> Vec<Defect> {
+    let rng = fastrand::Rng::with_seed(seed);
+    let failed_qubits = graph
+        .primal
+        .nodes()
+        .filter(|q| !graph.unused_qubits.contains(q))
+        .filter(|_| rng.f64() < config.qubit_failure)
+        .collect_vec();
+    let failed_couplers = graph
+        .primal
+        .all_edges()
+        .filter(|e| *e.2)
+        .filter(|_| rng.f64() < config.coupler_failure)
+        .filter(|(n1, n2, _)| !failed_qubits.contains(n1) && !failed_qubits.contains(n2))
+        .map(|(n1, n2, _)| Defect::Coupler(n1.min(n2), n1.max(n2)))
+        .collect_vec();
+    failed_qubits
+        .into_iter()
+        .map(Defect::Qubit)
+        .chain(failed_couplers)
+        .collect()
+}
+
+/// Run the search on random defect sets of the graph topology.
+///
+/// The samples run in parallel. Once `cancel` is cancelled the samples not
+/// yet finished are left out and the report is partial. A sample whose
+/// search runs out of the time budget of the `algorithm` is kept with the
+/// best score among the patterns evaluated in time.
+pub fn monte_carlo<P: Pattern + Sync>(
+    graph: &SearchGraph,
+    patterns: &[P],
+    algorithm: &AlgorithmConfig,
+    config: &MonteCarloConfig,
+    cancel: &CancelToken,
+) -> MonteCarloReport {
+    let rng = fastrand::Rng::with_seed(config.seed);
+    let seeds = (0..config.n_samples).map(|_| rng.u64(..)).collect_vec();
+    let samples: Vec<_> = seeds
+        .into_par_iter()
+        .filter_map(|seed| run_sample(graph, patterns, algorithm, config, cancel, seed))
+        .collect();
+
+    let scores = samples.iter().filter_map(|s| s.score).collect_vec();
+    let mut wins = HashMap::new();
+    for pattern in samples.iter().flat_map(|s| &s.best_patterns) {
+        *wins.entry(pattern.clone()).or_insert(0) += 1;
+    }
+    let pattern_wins = wins
+        .into_iter()
+        .sorted_by(|(p1, n1), (p2, n2)| n2.cmp(n1).then(p1.cmp(p2)))
+        .collect();
+    MonteCarloReport {
+        config: *config,
+        n_discarded: samples.len() - scores.len(),
+        partial: samples.len() < config.n_samples,
+        samples,
+        scores: ScoreDistribution::new(&scores),
+        pattern_wins,
+    }
+}
+
+/// Draw the defects of a sample and search the topology with them, `None`
+/// if the run is cancelled before the sample is finished.
+fn run_sample<P: Pattern + Sync>(
+    graph: &SearchGraph,
+    patterns: &[P],
+    algorithm: &AlgorithmConfig,
+    config: &MonteCarloConfig,
+    cancel: &CancelToken,
+    seed: u64,
+) -> Option<MonteCarloSample> {
+    if cancel.is_cancelled() {
+        return None;
+    }
+    let defects = draw_defects(graph, config, seed);
+    let mut topology = graph.config.clone();
+    topology.defects.extend(defects.iter().cloned());
+    topology.drop_islands |= config.disconnected == Disconnected::DropIslands;
+    let mut sample = MonteCarloSample {
+        seed,
+        defects,
+        score: None,
+        best_patterns: Vec::new(),
+        n_cutlines: 0,
+        n_dropped: 0,
+        partial: false,
+        error: None,
+    };
+    let variant = match SearchGraph::from_config(topology) {
+        Ok(variant) => variant,
+        Err(e) => {
+            let error = match e {
+                GraphError::Disconnected { components } => {
+                    format!("{} connected components", components.len())
+                }
+                e => e.to_string(),
+            };
+            sample.error = Some(error);
+            return Some(sample);
+        }
+    };
+    sample.n_dropped = variant
+        .dropped_islands
+        .iter()
+        .map(|island| island.qubits.len())
+        .sum();
+    let cutlines = match search_cutlines(&variant, algorithm) {
+        Ok(cutlines) => cutlines,
+        Err(e) => {
+            sample.error = Some(e.to_string());
+            return Some(sample);
+        }
+    };
+    sample.n_cutlines = cutlines.len();
+    if cutlines.is_empty() {
+        sample.error = Some("no valid cutline".to_string());
+        return Some(sample);
+    }
+    let cutlines_wrapped = cutlines
+        .into_iter()
+        .map(|c| c.into_wrapped(&variant))
+        .collect_vec();
+    let model = algorithm.cost_model.build(&variant);
+    let result = max_min_cost_wrapped(
+        &variant,
+        patterns,
+        &cutlines_wrapped,
+        algorithm,
+        model.as_ref(),
+        &SilentProgress,
+        cancel,
+    );
+    if cancel.is_cancelled() {
+        return None;
+    }
+    sample.partial = result.partial;
+    if result.partial && result.records.is_empty() {
+        sample.error = Some("no pattern evaluated in the time budget".to_string());
+        return Some(sample);
+    }
+    sample.score = result.records.first().map(|r| r.score);
+    sample.best_patterns = result
+        .records
+        .into_iter()
+        .map(|r| r.pattern)
+        .dedup()
+        .collect();
+    Some(sample)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AlgorithmConfigBuilder, TopologyConfigBuilder};
+    use crate::search_pattern::search_bit_patterns;
+
+    #[test]
+    fn test_monte_carlo() {
+        let topology = TopologyConfigBuilder::default()
+            .width(5)
+            .height(5)
+            .build()
+            .unwrap();
+        let graph = SearchGraph::from_config(topology).unwrap();
+        let algo = AlgorithmConfigBuilder::default()
+            .max_depth(5)
+            .build()
+            .unwrap();
+        let patterns = search_bit_patterns(&graph).collect_vec();
+        let mut config = MonteCarloConfig {
+            n_samples: 8,
+            seed: 7,
+            qubit_failure: 0.1,
+            coupler_failure: 0.1,
+            disconnected: Disconnected::Discard,
+        };
+        let cancel = CancelToken::new();
+        let report = monte_carlo(&graph, &patterns, &algo, &config, &cancel);
+        assert_eq!(report.samples.len(), 8);
+        assert!(!report.partial);
+        assert_eq!(
+            report.n_discarded,
+            report.samples.iter().filter(|s| s.error.is_some()).count()
+        );
+        let scores = report.scores.unwrap();
+        assert!(scores.min <= scores.median && scores.median <= scores.max);
+        assert_eq!(
+            report.pattern_wins.iter().map(|(_, n)| n).sum::<usize>(),
+            report
+                .samples
+                .iter()
+                .map(|s| s.best_patterns.len())
+                .sum::<usize>()
+        );
+
+        // reproducible from the seed of the run and of each sample
+        assert_eq!(
+            monte_carlo(&graph, &patterns, &algo, &config, &cancel),
+            report
+        );
+        for sample in &report.samples {
+            assert_eq!(draw_defects(&graph, &config, sample.seed), sample.defects);
+        }
+
+        // nothing fails without failure probability
+        config.qubit_failure = 0.0;
+        config.coupler_failure = 0.0;
+        config.n_samples = 1;
+        let report = monte_carlo(&graph, &patterns, &algo, &config, &cancel);
+        assert!(report.samples[0].defects.is_empty());
+        assert_eq!(report.scores.unwrap().min, report.scores.unwrap().max);
+
+        // every qubit fails, the islands cannot be dropped either
+        config.qubit_failure = 1.0;
+        config.disconnected = Disconnected::DropIslands;
+        let report = monte_carlo(&graph, &patterns, &algo, &config, &cancel);
+        assert_eq!(report.n_discarded, 1);
+        assert!(report.scores.is_none());
+
+        // the samples out of time are kept, the cancelled ones are not
+        config.qubit_failure = 0.0;
+        config.n_samples = 2;
+        let mut no_time = algo.clone();
+        no_time.time_budget = Some(0);
+        let report = monte_carlo(&graph, &patterns, &no_time, &config, &cancel);
+        assert!(!report.partial);
+        assert!(report
+            .samples
+            .iter()
+            .all(|s| s.partial && s.error.is_some()));
+        cancel.cancel();
+        let report = monte_carlo(&graph, &patterns, &algo, &config, &cancel);
+        assert!(report.partial);
+        assert!(report.samples.is_empty());
+    }
+}
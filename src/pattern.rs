@@ -2,6 +2,9 @@ use crate::graph::{Point, SearchGraph};
 use fixedbitset::FixedBitSet;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize, PartialOrd, Ord)]
 pub enum Order {
@@ -31,33 +34,219 @@ impl TryFrom<char> for Order {
 }
 
 #[derive(Debug, Clone, Copy)]
-pub struct Context {
+pub struct Context<'a> {
     qubit_at_origin: bool,
-    width: u32,
     height: u32,
     n_slash: usize,
+    graph: &'a SearchGraph,
 }
 
-impl Context {
-    pub fn from_graph(graph: &crate::graph::SearchGraph) -> Self {
+impl<'a> Context<'a> {
+    pub fn from_graph(graph: &'a SearchGraph) -> Self {
         Context {
             qubit_at_origin: graph.config.qubit_at_origin,
-            width: graph.config.width,
             height: graph.config.height,
             n_slash: graph.num_slash(),
+            graph,
         }
     }
 }
 
+/// Serializable description of a pattern, carried by the search results.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternDescription {
+    /// Stripe pattern in the form of [`pattern_repr`]
+    Bit(BitRepr),
+    /// Heavy-hex pattern in the form of [`HexPattern::repr`]
+    Hex(String),
+    /// Square lattice pattern in the form of [`SquarePattern::repr`]
+    Square(String),
+    /// Order of every coupler, indexed by the edge index of the graph
+    Coloring(Vec<Option<Order>>),
+    /// Pattern of the tiles of a modular topology and the order of every
+    /// link, see [`LinkedPattern`]
+    Linked {
+        tiles: Box<PatternDescription>,
+        links: Vec<Option<Order>>,
+    },
+}
+
+/// The form of a bit pattern given by [`pattern_repr`], parsed once to
+/// order the patterns by their integer value.
+///
+/// It is serialized as the plain string, and a string which is not a valid
+/// form is rejected when deserialized.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct BitRepr {
+    repr: String,
+    /// Set bits from the highest to the lowest, comparing them
+    /// lexicographically compares the patterns by their integer value
+    descending_ones: Vec<usize>,
+}
+
+impl BitRepr {
+    pub fn new(pattern: &BitPattern, n_slash: usize) -> Self {
+        Self::from_pattern(pattern_repr(pattern, n_slash), pattern)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.repr
+    }
+
+    fn from_pattern(repr: String, pattern: &BitPattern) -> Self {
+        let mut descending_ones = pattern.ones().collect_vec();
+        descending_ones.reverse();
+        BitRepr {
+            repr,
+            descending_ones,
+        }
+    }
+}
+
+impl FromStr for BitRepr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pattern = pattern_from_repr(s)?;
+        Ok(Self::from_pattern(s.to_string(), &pattern))
+    }
+}
+
+impl TryFrom<String> for BitRepr {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<BitRepr> for String {
+    fn from(value: BitRepr) -> Self {
+        value.repr
+    }
+}
+
+impl fmt::Display for BitRepr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.repr)
+    }
+}
+
+impl Ord for BitRepr {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.descending_ones
+            .cmp(&other.descending_ones)
+            .then_with(|| self.repr.cmp(&other.repr))
+    }
+}
+
+impl PartialOrd for BitRepr {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Bit patterns come first ordered by their integer value, then the
+/// heavy-hex patterns, the square patterns and the colorings ordered
+/// lexicographically. The linked patterns come last, ordered by the pattern
+/// of their tiles and then by the orders of their links.
+impl Ord for PatternDescription {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let rank = |d: &Self| match d {
+            PatternDescription::Bit(_) => 0,
+            PatternDescription::Hex(_) => 1,
+            PatternDescription::Square(_) => 2,
+            PatternDescription::Coloring(_) => 3,
+            PatternDescription::Linked { .. } => 4,
+        };
+        match (self, other) {
+            (PatternDescription::Bit(r1), PatternDescription::Bit(r2)) => r1.cmp(r2),
+            (PatternDescription::Hex(r1), PatternDescription::Hex(r2))
+            | (PatternDescription::Square(r1), PatternDescription::Square(r2)) => r1.cmp(r2),
+            (PatternDescription::Coloring(o1), PatternDescription::Coloring(o2)) => o1.cmp(o2),
+            (
+                PatternDescription::Linked {
+                    tiles: t1,
+                    links: l1,
+                },
+                PatternDescription::Linked {
+                    tiles: t2,
+                    links: l2,
+                },
+            ) => t1.cmp(t2).then_with(|| l1.cmp(l2)),
+            _ => rank(self).cmp(&rank(other)),
+        }
+    }
+}
+
+impl PartialOrd for PatternDescription {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for PatternDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternDescription::Bit(repr) => write!(f, "{}", repr),
+            PatternDescription::Hex(repr) | PatternDescription::Square(repr) => {
+                write!(f, "{}", repr)
+            }
+            PatternDescription::Coloring(orders) => write_orders(f, orders),
+            PatternDescription::Linked { tiles, links } => {
+                write!(f, "{}+", tiles)?;
+                write_orders(f, links)
+            }
+        }
+    }
+}
+
+/// The orders as letters, `-` for a coupler without order.
+fn write_orders(f: &mut fmt::Formatter<'_>, orders: &[Option<Order>]) -> fmt::Result {
+    orders.iter().try_for_each(|o| match o {
+        Some(o) => write!(f, "{:?}", o),
+        None => write!(f, "-"),
+    })
+}
+
+/// Parse a coloring in the form of its [`PatternDescription`], one letter
+/// per coupler in the order of the edge index, `-` for a coupler without
+/// order.
+pub fn coloring_from_repr(repr: &str, n_couplers: usize) -> Result<VecPattern, String> {
+    let coloring = repr
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '-' => Ok(None),
+            c => Order::try_from(c).map(Some),
+        })
+        .collect::<Result<VecPattern, _>>()?;
+    if coloring.len() != n_couplers {
+        return Err(format!(
+            "the coloring '{}' has {} orders, the graph has {} couplers",
+            repr,
+            coloring.len(),
+            n_couplers
+        ));
+    }
+    Ok(coloring)
+}
+
 pub trait Pattern {
     fn look_up(&self, n1: Point, n2: Point, context: &Context) -> Option<Order>;
 
+    fn describe(&self, graph: &SearchGraph) -> PatternDescription;
+
+    /// The order of every used coupler, indexed by the edge index. The links
+    /// between tiles are left without order, see [`LinkedPattern`].
     fn order_vec(&self, graph: &SearchGraph) -> Vec<Option<Order>> {
         let context = Context::from_graph(graph);
         let primal = &graph.primal;
         let mut order_vec = vec![None; primal.edge_count()];
         primal.all_edges().for_each(|(n1, n2, &is_real)| {
-            if is_real {
+            if is_real && !graph.is_link(n1, n2) {
                 let order = self.look_up(n1, n2, &context);
                 let index = graph.edge_index(n1, n2);
                 order_vec[index] = order;
@@ -72,14 +261,53 @@ pub type VecPattern = Vec<Option<Order>>;
 
 impl Pattern for VecPattern {
     fn look_up(&self, n1: Point, n2: Point, context: &Context) -> Option<Order> {
-        let index = get_edge_index(n1, n2, (context.width - 1) as usize);
-        self[index]
+        self[context.graph.edge_index(n1, n2)]
     }
+
+    fn describe(&self, _graph: &SearchGraph) -> PatternDescription {
+        PatternDescription::Coloring(self.clone())
+    }
+
+    fn order_vec(&self, _graph: &SearchGraph) -> Vec<Option<Order>> {
+        self.clone()
+    }
+}
+
+/// A pattern of the tiles of a modular topology, such as a [`BitPattern`]
+/// of the whole grid, with the orders of the links between the tiles, see
+/// [`search_linked_patterns`](crate::search_pattern::search_linked_patterns).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LinkedPattern<P> {
+    pub tiles: P,
+    /// Order of every link, in the order of [`SearchGraph::links`]
+    pub links: Vec<Option<Order>>,
 }
 
-pub fn get_edge_index(n1: Point, n2: Point, edges_per_line: usize) -> usize {
-    let index = (n1.1 + n2.1 - 1) / 2 * edges_per_line as i32 + (n1.0 + n2.0 - 1) / 2;
-    index as usize
+impl<P: Pattern> Pattern for LinkedPattern<P> {
+    fn look_up(&self, n1: Point, n2: Point, context: &Context) -> Option<Order> {
+        let link = (n1.min(n2), n1.max(n2));
+        match context.graph.links.iter().position(|&l| l == link) {
+            Some(i) => self.links[i],
+            None => self.tiles.look_up(n1, n2, context),
+        }
+    }
+
+    fn describe(&self, graph: &SearchGraph) -> PatternDescription {
+        PatternDescription::Linked {
+            tiles: Box::new(self.tiles.describe(graph)),
+            links: self.links.clone(),
+        }
+    }
+
+    fn order_vec(&self, graph: &SearchGraph) -> Vec<Option<Order>> {
+        let mut order_vec = self.tiles.order_vec(graph);
+        for (&(n1, n2), &order) in graph.links.iter().zip(&self.links) {
+            if *graph.primal.edge_weight(n1, n2).unwrap() {
+                order_vec[graph.edge_index(n1, n2)] = order;
+            }
+        }
+        order_vec
+    }
 }
 
 pub type BitPattern = FixedBitSet;
@@ -108,9 +336,212 @@ impl Pattern for BitPattern {
             (true, true) => Some(Order::B),
         }
     }
+
+    fn describe(&self, graph: &SearchGraph) -> PatternDescription {
+        PatternDescription::Bit(BitRepr::new(self, graph.num_slash()))
+    }
+}
+
+/// Coupler activation pattern of a heavy-hex lattice, see
+/// [`heavy_hex_layout`](crate::graph::heavy_hex_layout).
+///
+/// The couplers along the chain `r` alternate between two orders, swapped by
+/// the bit `1 + r`. The upper and lower couplers of the bridge qubits between
+/// the chains `r` and `r + 1` take the two other orders, swapped by the bit
+/// `1 + height + r`. The bit 0 swaps the orders A and B of the chains with
+/// the orders C and D of the bridges. Every qubit has at most one bridge
+/// coupler, so that no two couplers of a qubit share an order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HexPattern(pub FixedBitSet);
+
+impl HexPattern {
+    /// The pattern of the integer `n` on `height` chains, which has
+    /// `2 * height` bits.
+    pub fn from_bits(n: u32, height: u32) -> Self {
+        HexPattern(FixedBitSet::with_capacity_and_blocks(
+            2 * height as usize,
+            vec![n],
+        ))
+    }
+
+    /// The bits in the form of `flip_chains_bridges`, such as `0_0110_101`
+    /// for 4 chains.
+    pub fn repr(&self) -> String {
+        stripe_repr(&self.0, self.0.len() / 2)
+    }
+
+    /// Parse the form of [`HexPattern::repr`], checking it has a bit for
+    /// each of the `height` chains.
+    pub fn from_repr(repr: &str, height: u32) -> Result<Self, String> {
+        let pattern: HexPattern = repr.parse()?;
+        let chains = pattern.0.len() / 2;
+        if chains != height as usize {
+            return Err(format!(
+                "the heavy-hex pattern '{}' has {} chains, the lattice has {}",
+                repr, chains, height
+            ));
+        }
+        Ok(pattern)
+    }
+}
+
+impl FromStr for HexPattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match stripe_from_repr(s) {
+            Some((bits, chains, bridges)) if chains == bridges + 1 => Ok(HexPattern(bits)),
+            _ => Err(format!("invalid heavy-hex pattern '{}'", s)),
+        }
+    }
+}
+
+impl Pattern for HexPattern {
+    fn look_up(&self, n1: Point, n2: Point, context: &Context) -> Option<Order> {
+        let (n1, n2) = (n1.min(n2), n1.max(n2));
+        let is_chain = n1.1 == n2.1;
+        let index = hex_bit_index(n1, n2, context.height);
+        let parity = if is_chain {
+            n1.0.rem_euclid(2) == 1
+        } else {
+            // the lower coupler of a bridge qubit starts from it
+            n1.1.rem_euclid(2) == 1
+        };
+        Some(stripe_order(is_chain, self.0[0], parity ^ self.0[index]))
+    }
+
+    fn describe(&self, _graph: &SearchGraph) -> PatternDescription {
+        PatternDescription::Hex(self.repr())
+    }
+}
+
+/// Coupler activation pattern of an axis-aligned square lattice, see
+/// [`square_layout`](crate::graph::square_layout).
+///
+/// The horizontal couplers of the row `y` alternate between two orders,
+/// swapped by the bit `1 + y`, and the vertical couplers of the column `x`
+/// between the two other orders, swapped by the bit `1 + height + x`. The
+/// bit 0 swaps the orders A and B of the rows with the orders C and D of the
+/// columns.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SquarePattern(pub FixedBitSet);
+
+impl SquarePattern {
+    /// The pattern of the integer `n` on a `width` x `height` lattice, which
+    /// has `1 + height + width` bits.
+    pub fn from_bits(n: u32, width: u32, height: u32) -> Self {
+        SquarePattern(FixedBitSet::with_capacity_and_blocks(
+            (1 + width + height) as usize,
+            vec![n],
+        ))
+    }
+
+    /// The bits in the form of `flip_rows_columns`, such as `0_010_1101` for
+    /// a 4 x 3 lattice.
+    pub fn repr(&self, height: u32) -> String {
+        stripe_repr(&self.0, height as usize)
+    }
+
+    /// Parse the form of [`SquarePattern::repr`], checking it has a bit for
+    /// each row and each column of a `width` x `height` lattice.
+    pub fn from_repr(repr: &str, width: u32, height: u32) -> Result<Self, String> {
+        match stripe_from_repr(repr) {
+            Some((bits, rows, columns)) if (rows, columns) == (height as usize, width as usize) => {
+                Ok(SquarePattern(bits))
+            }
+            Some((_, rows, columns)) => Err(format!(
+                "the square pattern '{}' has {} rows and {} columns, the lattice has {} and {}",
+                repr, rows, columns, height, width
+            )),
+            None => Err(format!("invalid square pattern '{}'", repr)),
+        }
+    }
+}
+
+impl FromStr for SquarePattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match stripe_from_repr(s) {
+            Some((bits, _, _)) => Ok(SquarePattern(bits)),
+            None => Err(format!("invalid square pattern '{}'", s)),
+        }
+    }
+}
+
+impl Pattern for SquarePattern {
+    fn look_up(&self, n1: Point, n2: Point, context: &Context) -> Option<Order> {
+        let (n1, n2) = (n1.min(n2), n1.max(n2));
+        let is_row = n1.1 == n2.1;
+        let index = square_bit_index(n1, n2, context.height);
+        let parity = if is_row { n1.0 } else { n1.1 }.rem_euclid(2) == 1;
+        Some(stripe_order(is_row, self.0[0], parity ^ self.0[index]))
+    }
+
+    fn describe(&self, graph: &SearchGraph) -> PatternDescription {
+        PatternDescription::Square(self.repr(graph.config.height))
+    }
+}
+
+/// The bit of a [`SquarePattern`] swapping the orders of the coupler.
+pub fn square_bit_index(n1: Point, n2: Point, height: u32) -> usize {
+    if n1.1 == n2.1 {
+        1 + n1.1 as usize
+    } else {
+        1 + height as usize + n1.0 as usize
+    }
+}
+
+/// Order of a coupler of a stripe pattern, the flip bit swaps the orders A
+/// and B of the horizontal couplers with the orders C and D of the others.
+fn stripe_order(is_horizontal: bool, flip: bool, parity: bool) -> Order {
+    // parity == 0 => A|C , 1 => B|D
+    match (flip ^ is_horizontal, parity) {
+        (false, false) => Order::C,
+        (false, true) => Order::D,
+        (true, false) => Order::A,
+        (true, true) => Order::B,
+    }
+}
+
+/// The bits of a stripe pattern in the form of `flip_horizontal_others`.
+fn stripe_repr(bits: &FixedBitSet, n_horizontal: usize) -> String {
+    let raw = bits.to_string();
+    let (flip, remain) = raw.split_at(1);
+    let (horizontal, others) = remain.split_at(n_horizontal);
+    [flip, horizontal, others].join("_")
+}
+
+/// Parse the form of [`stripe_repr`] into the bits and the number of
+/// horizontal and other stripes.
+fn stripe_from_repr(repr: &str) -> Option<(FixedBitSet, usize, usize)> {
+    match repr.split('_').collect_vec()[..] {
+        [flip, horizontal, others] if flip.len() == 1 => {
+            let raw = [flip, horizontal, others].concat();
+            let mut bits = FixedBitSet::with_capacity(raw.len());
+            for (i, c) in raw.char_indices() {
+                match c {
+                    '0' => {}
+                    '1' => bits.insert(i),
+                    _ => return None,
+                }
+            }
+            Some((bits, horizontal.len(), others.len()))
+        }
+        _ => None,
+    }
+}
+
+/// The bit of a [`HexPattern`] swapping the orders of the coupler.
+pub fn hex_bit_index(n1: Point, n2: Point, height: u32) -> usize {
+    let row = n1.1.min(n2.1) as usize / 2;
+    if n1.1 == n2.1 {
+        1 + row
+    } else {
+        1 + height as usize + row
+    }
 }
 
-#[allow(unused)]
 pub fn pattern_repr(pattern: &BitPattern, n_slash: usize) -> String {
     let last_flip: &str = if pattern[0] { "0" } else { "1" };
     let raw = pattern.to_string();
@@ -119,23 +550,29 @@ pub fn pattern_repr(pattern: &BitPattern, n_slash: usize) -> String {
     if pattern[0] {
         std::mem::swap(&mut middle, &mut last);
     }
-    vec![first, "_", middle, "_", last_flip, "_", last].join("")
+    [first, "_", middle, "_", last_flip, "_", last].join("")
 }
 
-pub fn pattern_from_repr(repr: &str) -> BitPattern {
-    let splitted = repr.split('_').collect_vec();
-    let (mut middle, mut last) = (splitted[1], splitted[3]);
-    if splitted[0] == "1" {
+/// Parse the form of [`pattern_repr`], such as `1_0100110010_0_1001100101`.
+pub fn pattern_from_repr(repr: &str) -> Result<BitPattern, String> {
+    let [flip, mut middle, last_flip, mut last] = repr.split('_').collect_vec()[..] else {
+        return Err(format!("invalid bit pattern '{}'", repr));
+    };
+    let is_bit = |c: char| c == '0' || c == '1' || c == '_';
+    if flip.len() != 1 || last_flip.len() != 1 || !repr.chars().all(is_bit) {
+        return Err(format!("invalid bit pattern '{}'", repr));
+    }
+    if flip == "1" {
         std::mem::swap(&mut middle, &mut last);
     }
-    let bin_str = vec![splitted[0], middle, last].join("");
+    let bin_str = [flip, middle, last].join("");
     let mut pattern = BitPattern::with_capacity(bin_str.len());
     for (i, c) in bin_str.char_indices() {
         if c == '1' {
             pattern.put(i);
         }
     }
-    pattern
+    Ok(pattern)
 }
 
 pub fn slash_index(
@@ -162,15 +599,18 @@ pub fn slash_index(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{config::TopologyConfigBuilder, graph::SearchGraph};
+    use crate::{
+        config::TopologyConfigBuilder,
+        graph::{Lattice, SearchGraph},
+    };
 
     macro_rules! trivial_pattern_test {
         ($graph:ident, $pattern:ident, $orders:expr) => {
             let context = Context {
                 qubit_at_origin: false,
-                width: $graph.config.width,
                 height: $graph.config.height,
                 n_slash: $graph.num_slash(),
+                graph: &$graph,
             };
             $graph
                 .primal
@@ -209,9 +649,9 @@ mod tests {
 
         let context = Context {
             qubit_at_origin: false,
-            width: graph.config.width,
             height: graph.config.height,
             n_slash: graph.num_slash(),
+            graph: &graph,
         };
         let mut pattern = BitPattern::with_capacity_and_blocks(21, vec![0]);
         pattern.put(20);
@@ -221,11 +661,103 @@ mod tests {
     }
 
     #[test]
-    fn test_get_edge_index() {
+    fn test_vec_pattern_look_up() {
         let graph = SearchGraph::default();
-        let edges_per_line = (graph.config.width - 1) as usize;
-        assert_eq!(get_edge_index((1, 0), (0, 1), edges_per_line), 0);
-        assert_eq!(get_edge_index((10, 9), (11, 10), edges_per_line), 109);
+        let context = Context::from_graph(&graph);
+        let mut pattern: VecPattern = vec![None; graph.edges.len()];
+        pattern[0] = Some(Order::A);
+        pattern[109] = Some(Order::D);
+        assert_eq!(pattern.look_up((1, 0), (0, 1), &context), Some(Order::A));
+        assert_eq!(pattern.look_up((10, 9), (11, 10), &context), Some(Order::D));
+        assert_eq!(pattern.look_up((3, 2), (2, 1), &context), None);
+
+        let repr = pattern.describe(&graph).to_string();
+        assert_eq!(coloring_from_repr(&repr, graph.edges.len()), Ok(pattern));
+        assert!(coloring_from_repr(&repr[1..], graph.edges.len()).is_err());
+        assert!(coloring_from_repr(&repr.replace('-', "E"), graph.edges.len()).is_err());
+    }
+
+    #[test]
+    fn test_hex_pattern_look_up() {
+        let config = TopologyConfigBuilder::default()
+            .width(7)
+            .height(3)
+            .lattice(Lattice::HeavyHex)
+            .build()
+            .unwrap();
+        let graph = SearchGraph::from_config(config).unwrap();
+        let context = Context::from_graph(&graph);
+        let pattern = HexPattern::from_bits(0, 3);
+        assert_eq!(pattern.look_up((0, 0), (1, 0), &context), Some(Order::A));
+        assert_eq!(pattern.look_up((2, 0), (1, 0), &context), Some(Order::B));
+        assert_eq!(pattern.look_up((0, 0), (0, 1), &context), Some(Order::C));
+        assert_eq!(pattern.look_up((0, 1), (0, 2), &context), Some(Order::D));
+
+        // every pattern activates the couplers of a qubit at different times
+        for n in 0..1 << 6 {
+            let pattern = HexPattern::from_bits(n, 3);
+            assert_eq!(pattern.repr().parse::<HexPattern>(), Ok(pattern.clone()));
+            for node in graph.primal.nodes() {
+                let orders = graph
+                    .primal
+                    .neighbors(node)
+                    .map(|n| pattern.look_up(node, n, &context))
+                    .collect_vec();
+                assert!(orders.iter().all_unique());
+            }
+        }
+        let pattern: HexPattern = "1_010_10".parse().unwrap();
+        assert_eq!(pattern.repr(), "1_010_10");
+        assert_eq!(pattern.look_up((0, 0), (1, 0), &context), Some(Order::C));
+        assert_eq!(pattern.look_up((0, 2), (1, 2), &context), Some(Order::D));
+        assert_eq!(pattern.look_up((0, 1), (0, 2), &context), Some(Order::A));
+        assert!("1_010_1".parse::<HexPattern>().is_err());
+        // the pattern of another number of chains
+        assert_eq!(HexPattern::from_repr("1_010_10", 3), Ok(pattern));
+        assert!(HexPattern::from_repr("1_0101_101", 3).is_err());
+    }
+
+    #[test]
+    fn test_square_pattern_look_up() {
+        let config = TopologyConfigBuilder::default()
+            .width(5)
+            .height(4)
+            .lattice(Lattice::Square)
+            .build()
+            .unwrap();
+        let graph = SearchGraph::from_config(config).unwrap();
+        let context = Context::from_graph(&graph);
+        let pattern = SquarePattern::from_bits(0, 5, 4);
+        assert_eq!(pattern.look_up((0, 0), (1, 0), &context), Some(Order::A));
+        assert_eq!(pattern.look_up((2, 3), (1, 3), &context), Some(Order::B));
+        assert_eq!(pattern.look_up((4, 0), (4, 1), &context), Some(Order::C));
+        assert_eq!(pattern.look_up((4, 2), (4, 1), &context), Some(Order::D));
+
+        for n in (0..1 << 10).step_by(7) {
+            let pattern = SquarePattern::from_bits(n, 5, 4);
+            let repr = pattern.repr(4);
+            assert_eq!(repr.parse::<SquarePattern>(), Ok(pattern.clone()));
+            assert_eq!(pattern.describe(&graph), PatternDescription::Square(repr));
+            for node in graph.primal.nodes() {
+                let orders = graph
+                    .primal
+                    .neighbors(node)
+                    .map(|n| pattern.look_up(node, n, &context))
+                    .collect_vec();
+                assert!(orders.iter().all_unique());
+            }
+        }
+        let pattern: SquarePattern = "1_0100_00001".parse().unwrap();
+        assert_eq!(pattern.look_up((0, 1), (1, 1), &context), Some(Order::D));
+        assert_eq!(pattern.look_up((4, 0), (4, 1), &context), Some(Order::B));
+        assert!("1_0100".parse::<SquarePattern>().is_err());
+        // the rows and columns of another lattice
+        assert_eq!(SquarePattern::from_repr("1_0100_00001", 5, 4), Ok(pattern));
+        assert_eq!(
+            SquarePattern::from_repr("1_01000_0001", 5, 4),
+            Err("the square pattern '1_01000_0001' has 5 rows and 4 columns, the lattice has 4 and 5".to_string())
+        );
+        assert!(SquarePattern::from_repr("1_0100_0001", 5, 4).is_err());
     }
 
     #[test]
@@ -234,22 +766,57 @@ mod tests {
         let mut pattern = BitPattern::with_capacity_and_blocks(21, vec![0]);
         assert_eq!(pattern.to_string(), "000000000000000000000");
         assert_eq!(pattern_repr(&pattern, n_slash), "0_0000000000_1_0000000000");
-        assert_eq!(pattern_from_repr("0_0000000000_1_0000000000"), pattern);
+        assert_eq!(
+            pattern_from_repr("0_0000000000_1_0000000000"),
+            Ok(pattern.clone())
+        );
 
         pattern.put(0);
         assert_eq!(pattern.to_string(), "100000000000000000000");
         assert_eq!(pattern_repr(&pattern, n_slash), "1_0000000000_0_0000000000");
-        assert_eq!(pattern_from_repr("1_0000000000_0_0000000000"), pattern);
+        assert_eq!(
+            pattern_from_repr("1_0000000000_0_0000000000"),
+            Ok(pattern.clone())
+        );
 
         pattern.put(1);
         assert_eq!(pattern.to_string(), "110000000000000000000");
         assert_eq!(pattern_repr(&pattern, n_slash), "1_0000000000_0_1000000000");
-        assert_eq!(pattern_from_repr("1_0000000000_0_1000000000"), pattern);
+        assert_eq!(
+            pattern_from_repr("1_0000000000_0_1000000000"),
+            Ok(pattern.clone())
+        );
+        assert!(pattern_from_repr("0101").is_err());
+        assert!(pattern_from_repr("1_0100_0_0120").is_err());
+        assert!(pattern_from_repr("10_0100_0_0100").is_err());
         let graph = SearchGraph::default();
         let context = Context::from_graph(&graph);
         assert_eq!(pattern.look_up((1, 0), (0, 1), &context), Some(Order::D));
     }
 
+    #[test]
+    fn test_pattern_description_order() {
+        let n_slash = 10;
+        let descriptions = [0u32, 1, 2, 3, 1 << 11, (1 << 11) | 1, 1 << 20]
+            .into_iter()
+            .map(|n| {
+                let pattern = BitPattern::with_capacity_and_blocks(21, vec![n]);
+                PatternDescription::Bit(BitRepr::new(&pattern, n_slash))
+            })
+            .collect_vec();
+        assert!(descriptions.windows(2).all(|w| w[0] < w[1]));
+        let coloring = PatternDescription::Coloring(vec![None, Some(Order::A)]);
+        assert!(descriptions[6] < coloring);
+        assert!(PatternDescription::Coloring(vec![Some(Order::A), Some(Order::B)]) > coloring);
+
+        // the bit form is kept as a string and checked when deserialized
+        let json = serde_json::to_string(&descriptions[5]).unwrap();
+        assert_eq!(json, r#"{"bit":"1_1000000000_0_0000000000"}"#);
+        let parsed: PatternDescription = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, descriptions[5]);
+        assert!(serde_json::from_str::<PatternDescription>(r#"{"bit":"0101"}"#).is_err());
+    }
+
     #[test]
     fn test_pattern_corresponds_to_js() {
         let pattern = BitPattern::with_capacity_and_blocks(21, vec![3]);
@@ -260,7 +827,7 @@ mod tests {
         let config = TopologyConfigBuilder::default().height(12).build().unwrap();
         let graph = SearchGraph::from_config(config).unwrap();
         let context = Context::from_graph(&graph);
-        let pattern = pattern_from_repr("1_0100110010_0_10011001010");
+        let pattern = pattern_from_repr("1_0100110010_0_10011001010").unwrap();
         assert_eq!(
             pattern_repr(&pattern, graph.num_slash()),
             "1_0100110010_0_10011001010"
@@ -0,0 +1,161 @@
+use crate::config::{AlgorithmConfigBuilder, Config, TopologyConfigBuilder};
+use crate::pattern::Order;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The chips of published random circuit sampling experiments, with their
+/// dead qubits and the gate ordering of the experiment.
+///
+/// The qubit indices follow the row-major order of the rotated grid like
+/// `unused_qubits`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+    /// Sycamore, 53 of its 54 qubits with 20 cycles of ABCDCDAB
+    Sycamore53,
+    /// Zuchongzhi 2.0, 56 of its 66 qubits with 20 cycles of ABCDCDAB
+    Zuchongzhi56,
+    /// Zuchongzhi 2.1, 60 of its 66 qubits with 24 cycles of ABCDCDAB
+    Zuchongzhi60,
+    /// Zuchongzhi, all its 66 qubits with 20 cycles of ABCDCDAB
+    Zuchongzhi66,
+}
+
+impl Preset {
+    /// The topology and algorithm config of the chip, the maximum depth of
+    /// the cutlines is the longer side of the grid.
+    pub fn config(self) -> Config {
+        let (width, height, qubit_at_origin) = match self {
+            Preset::Sycamore53 => (12, 9, true),
+            Preset::Zuchongzhi56 | Preset::Zuchongzhi60 | Preset::Zuchongzhi66 => (12, 11, false),
+        };
+        let unused_qubits = match self {
+            Preset::Sycamore53 => vec![2],
+            Preset::Zuchongzhi56 => vec![0, 5, 6, 11, 48, 53, 54, 59, 60, 65],
+            Preset::Zuchongzhi60 => vec![0, 5, 54, 59, 60, 65],
+            Preset::Zuchongzhi66 => vec![],
+        };
+        let cycles = match self {
+            Preset::Zuchongzhi60 => 24,
+            _ => 20,
+        };
+        let topology = TopologyConfigBuilder::default()
+            .width(width)
+            .height(height)
+            .qubit_at_origin(qubit_at_origin)
+            .unused_qubits(unused_qubits)
+            .build()
+            .unwrap();
+        let algorithm = AlgorithmConfigBuilder::default()
+            .max_depth(width.max(height) as usize)
+            .ordering(abcdcdab(cycles))
+            .build()
+            .unwrap();
+        Config::new(topology, algorithm)
+    }
+}
+
+/// The ABCDCDAB ordering repeated over the cycles.
+fn abcdcdab(cycles: usize) -> Vec<Order> {
+    [
+        Order::A,
+        Order::B,
+        Order::C,
+        Order::D,
+        Order::C,
+        Order::D,
+        Order::A,
+        Order::B,
+    ]
+    .into_iter()
+    .cycle()
+    .take(cycles)
+    .collect()
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.to_possible_value().unwrap();
+        write!(f, "{}", name.get_name())
+    }
+}
+
+impl From<Preset> for Config {
+    fn from(preset: Preset) -> Self {
+        preset.config()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cutline::search_cutlines;
+    use crate::graph::SearchGraph;
+
+    /// Pin the used qubits and couplers, the primal and dual sizes, the
+    /// diagonals and the cutlines of the preset.
+    fn assert_preset(preset: Preset, expected: [usize; 9]) {
+        let config = preset.config();
+        let graph = SearchGraph::from_config(config.topology).unwrap();
+        let n_used_qubits = graph.primal.node_count() - graph.unused_qubits.len();
+        let n_used_couplers = graph.primal.all_edges().filter(|e| *e.2).count();
+        let n_cutlines = search_cutlines(&graph, &config.algorithm).unwrap().len();
+        assert_eq!(
+            [
+                n_used_qubits,
+                n_used_couplers,
+                graph.primal.node_count(),
+                graph.primal.edge_count(),
+                graph.dual.node_count(),
+                graph.dual.edge_count(),
+                graph.num_slash(),
+                graph.num_back_slash(),
+                n_cutlines,
+            ],
+            expected,
+            "{}",
+            preset
+        );
+    }
+
+    #[test]
+    fn test_sycamore53() {
+        assert_preset(Preset::Sycamore53, [53, 86, 54, 88, 54, 88, 9, 9, 13289]);
+    }
+
+    #[test]
+    fn test_zuchongzhi56() {
+        assert_preset(
+            Preset::Zuchongzhi56,
+            [56, 92, 66, 110, 58, 98, 10, 10, 15547],
+        );
+    }
+
+    #[test]
+    fn test_zuchongzhi60() {
+        assert_preset(
+            Preset::Zuchongzhi60,
+            [60, 100, 66, 110, 62, 105, 10, 10, 13453],
+        );
+    }
+
+    #[test]
+    fn test_zuchongzhi66() {
+        assert_preset(
+            Preset::Zuchongzhi66,
+            [66, 110, 66, 110, 66, 110, 10, 10, 11828],
+        );
+    }
+
+    #[test]
+    fn test_preset_config() {
+        let config = Config::from(Preset::Zuchongzhi60);
+        assert_eq!(config.algorithm.ordering.len(), 24);
+        assert_eq!(config.algorithm.ordering[..8], abcdcdab(8));
+        assert_eq!(config.algorithm.max_depth, 12);
+        assert_eq!(Preset::Sycamore53.to_string(), "sycamore53");
+        let json = serde_json::to_string(&Preset::Zuchongzhi56).unwrap();
+        assert_eq!(json, "\"zuchongzhi56\"");
+    }
+}
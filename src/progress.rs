@@ -0,0 +1,69 @@
+use indicatif::{ProgressBar, ProgressState, ProgressStyle};
+use std::fmt::Write;
+
+/// Receives the progress of [`max_min_cost`](crate::cost::max_min_cost).
+///
+/// The search evaluates patterns in parallel, so the callbacks can be invoked
+/// from several threads at the same time.
+pub trait ProgressObserver: Sync {
+    /// Called once before any pattern is evaluated.
+    fn on_start(&self, _n_patterns: usize, _n_cutlines: usize) {}
+
+    /// Called each time a pattern is evaluated, with the number of completed
+    /// patterns and the running best max-min score.
+    fn on_pattern_completed(&self, _completed: usize, _best_score: f64) {}
+
+    /// Called once after the search.
+    fn on_finish(&self) {}
+}
+
+/// Ignore all the progress events.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SilentProgress;
+
+impl ProgressObserver for SilentProgress {}
+
+/// Draw a progress bar to the terminal.
+#[derive(Debug, Clone)]
+pub struct TerminalProgress {
+    bar: ProgressBar,
+}
+
+impl TerminalProgress {
+    pub fn new() -> Self {
+        let bar = ProgressBar::new(0);
+        bar.set_style(
+            ProgressStyle::with_template(
+                "{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta}) {msg}",
+            )
+            .unwrap()
+            .with_key("eta", |state: &ProgressState, w: &mut dyn Write| {
+                write!(w, "{:.1}s", state.eta().as_secs_f64()).unwrap()
+            })
+            .progress_chars("#>-"),
+        );
+        Self { bar }
+    }
+}
+
+impl Default for TerminalProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProgressObserver for TerminalProgress {
+    fn on_start(&self, n_patterns: usize, _n_cutlines: usize) {
+        self.bar.set_length(n_patterns as u64);
+        self.bar.reset();
+    }
+
+    fn on_pattern_completed(&self, _completed: usize, best_score: f64) {
+        self.bar.set_message(format!("best: {:.4e}", best_score));
+        self.bar.inc(1);
+    }
+
+    fn on_finish(&self) {
+        self.bar.finish();
+    }
+}
@@ -0,0 +1,181 @@
+//! Serializable reports of the search results.
+//!
+//! A [`SearchReport`] serializes to the following JSON document, the
+//! `schema_version` is bumped whenever a field is renamed, removed or changes
+//! its meaning. Adding fields does not bump the version.
+//!
+//! ```json
+//! {
+//!   "schema_version": 1,
+//!   "cost_model": "standard",
+//!   "n_cutlines": 1234,
+//!   "n_patterns": 2097152,
+//!   "n_evaluated": 2097152,
+//!   "partial": false,
+//!   "dropped_qubits": [[11, 2]],
+//!   "records": [
+//!     {
+//!       "pattern": { "bit": "1_0100110010_0_1001100101" },
+//!       "cut_edges": [[[9, 2], [10, 3]], [[8, 3], [9, 4]]],
+//!       "partition": [[[1, 0], [3, 0]], [[11, 2], [10, 3]]],
+//!       "unbalance": 2,
+//!       "cost": { "gates": 40, "start_end": 2, "wedge": 3, "dcd": 1, "unbalance": 2 },
+//!       "score": 1.2e21
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! - `dropped_qubits` are the coordinates of the qubits of the islands
+//!   dropped with `drop_islands`.
+//! - `pattern` is either `{"bit": repr}` with the repr of
+//!   [`pattern_repr`](crate::pattern::pattern_repr), `{"hex": repr}` or
+//!   `{"square": repr}` with the repr of
+//!   [`HexPattern`](crate::pattern::HexPattern) or
+//!   [`SquarePattern`](crate::pattern::SquarePattern), or
+//!   `{"coloring": orders}` with the order (`"A"`..`"D"` or `null`) of every
+//!   coupler indexed by its edge index. On a modular topology it is
+//!   `{"linked": {"tiles": pattern, "links": orders}}`, with the pattern of
+//!   the tiles in one of the forms above and the order (`"A"`..`"D"` or
+//!   `null`) of every link between the tiles, in the order of
+//!   [`SearchGraph::links`].
+//! - `cut_edges` are the used couplers on the cutline as pairs of qubit
+//!   coordinates `[x, y]`.
+//! - `partition` are the two parts of the used qubits split by the cutline,
+//!   sorted in row-major order.
+//! - `cost` holds every field of [`Cost`], and `score` is the scalar cost
+//!   under `cost_model`.
+use crate::cost::{Cost, CostModelKind, Record, SearchResult};
+use crate::cutline::{split_parts, Edge};
+use crate::graph::{Point, SearchGraph};
+use crate::pattern::PatternDescription;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::Path;
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordReport {
+    pub pattern: PatternDescription,
+    pub cut_edges: Vec<Edge>,
+    pub partition: [Vec<Point>; 2],
+    pub unbalance: usize,
+    pub cost: Cost,
+    pub score: f64,
+}
+
+impl RecordReport {
+    pub fn new(record: &Record, graph: &SearchGraph) -> Self {
+        let primal = &graph.primal;
+        let cut_edges = record
+            .cutline
+            .split
+            .iter()
+            .filter(|e| *primal.edge_weight(e.0, e.1).unwrap())
+            .copied()
+            .collect();
+        let (part0, part1) = split_parts(&record.cutline.split, graph);
+        Self {
+            pattern: record.pattern.clone(),
+            cut_edges,
+            partition: [part0, part1],
+            unbalance: record.cutline.unbalance,
+            cost: record.cost,
+            score: record.score,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchReport {
+    pub schema_version: u32,
+    pub cost_model: CostModelKind,
+    pub n_cutlines: usize,
+    pub n_patterns: usize,
+    pub n_evaluated: usize,
+    pub partial: bool,
+    #[serde(default)]
+    pub dropped_qubits: Vec<Point>,
+    pub records: Vec<RecordReport>,
+}
+
+impl SearchReport {
+    pub fn new(
+        result: &SearchResult,
+        graph: &SearchGraph,
+        cost_model: CostModelKind,
+        n_cutlines: usize,
+        n_patterns: usize,
+    ) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            cost_model,
+            n_cutlines,
+            n_patterns,
+            n_evaluated: result.n_evaluated,
+            partial: result.partial,
+            dropped_qubits: graph
+                .dropped_islands
+                .iter()
+                .flat_map(|island| island.coordinates.iter().copied())
+                .collect(),
+            records: result
+                .records
+                .iter()
+                .map(|r| RecordReport::new(r, graph))
+                .collect(),
+        }
+    }
+
+    pub fn save_to_json(&self, path: &Path) -> Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cancel::CancelToken;
+    use crate::cost::max_min_cost;
+    use crate::pattern::pattern_from_repr;
+    use crate::progress::SilentProgress;
+    use crate::test_util::grid_search;
+
+    #[test]
+    fn test_record_report() {
+        let (graph, algo, cutlines) = grid_search(vec![3]);
+        let n_cutlines = cutlines.len();
+        let patterns = vec![pattern_from_repr("0_0110_1_100").unwrap()];
+        let result = max_min_cost(
+            &graph,
+            patterns,
+            cutlines,
+            &algo,
+            &SilentProgress,
+            &CancelToken::new(),
+        );
+        let report = SearchReport::new(&result, &graph, algo.cost_model, n_cutlines, 1);
+        let record = &report.records[0];
+        let [part0, part1] = &record.partition;
+        assert_eq!(part0.len() + part1.len(), 14);
+        assert_eq!(part0.len().abs_diff(part1.len()), record.unbalance);
+        assert!(!part0.contains(&(0, 1)) && !part1.contains(&(0, 1)));
+        assert!(record
+            .cut_edges
+            .iter()
+            .all(|(n1, n2)| part0.contains(n1) != part0.contains(n2)));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["schema_version"], SCHEMA_VERSION);
+        assert_eq!(json["cost_model"], "standard");
+        assert_eq!(json["dropped_qubits"], serde_json::json!([]));
+        assert_eq!(json["records"][0]["pattern"]["bit"], "0_0110_1_100");
+        assert!(json["records"][0]["cost"]["wedge"].is_u64());
+        let report2: SearchReport = serde_json::from_value(json).unwrap();
+        assert_eq!(report, report2);
+    }
+}
@@ -1,10 +1,15 @@
 use crate::{
+    cutline::Edge,
     graph::{Point, SearchGraph},
-    pattern::{get_edge_index, slash_index, BitPattern, Context, Order, Pattern, VecPattern},
+    pattern::{
+        hex_bit_index, slash_index, square_bit_index, BitPattern, Context, HexPattern,
+        LinkedPattern, Order, Pattern, SquarePattern, VecPattern,
+    },
 };
 use itertools::Itertools;
 use smallvec::SmallVec;
 use std::collections::HashSet;
+use std::iter::from_fn;
 
 pub fn search_bit_patterns(graph: &SearchGraph) -> impl Iterator<Item = BitPattern> {
     let n_slash = graph.num_slash();
@@ -25,7 +30,7 @@ fn dead_slash_indices(graph: &SearchGraph) -> Vec<usize> {
     let n_slash = graph.num_slash();
     let n_back_slash = graph.num_back_slash();
     graph.primal.all_edges().for_each(|(n1, n2, &weight)| {
-        if !weight {
+        if !weight || graph.is_link(n1, n2) {
             return;
         }
         let index = slash_index(
@@ -42,62 +47,180 @@ fn dead_slash_indices(graph: &SearchGraph) -> Vec<usize> {
         .collect()
 }
 
-#[allow(unused)]
-pub fn search_vec_patterns(graph: &SearchGraph) -> Vec<VecPattern> {
-    let n_edges = graph.primal.edge_count();
-    search_vec_patterns_rec(graph, vec![None; n_edges], HashSet::new())
+/// All the heavy-hex patterns of the graph, skipping the bits of the rows
+/// without used coupler.
+pub fn search_hex_patterns(graph: &SearchGraph) -> impl Iterator<Item = HexPattern> {
+    let height = graph.config.height;
+    let n_bits = 2 * height as usize;
+    if n_bits >= 32 {
+        panic!("Number of patterns is too large! The number of chains should be less than 16.");
+    }
+    let max_num: u32 = (1 << n_bits) - 1;
+    let live_bits: HashSet<_> = graph
+        .primal
+        .all_edges()
+        .filter(|e| *e.2)
+        .map(|(n1, n2, _)| hex_bit_index(n1, n2, height))
+        .collect();
+    let dead_indices = (1..n_bits).filter(|i| !live_bits.contains(i)).collect_vec();
+    (0..=max_num)
+        .filter(move |n| dead_indices.iter().all(|&i| n & (1 << i) == 0))
+        .map(move |n| HexPattern::from_bits(n, height))
 }
 
-fn search_vec_patterns_rec(
-    graph: &SearchGraph,
-    base_pattern: VecPattern,
-    mut searched_nodes: HashSet<Point>,
-) -> Vec<VecPattern> {
-    let mut patterns = Vec::new();
-    let primal = &graph.primal;
-    if primal.node_count() == searched_nodes.len() {
-        patterns.push(base_pattern);
-        return patterns;
-    }
-    let next_node = primal
-        .nodes()
-        .find(|n| !searched_nodes.contains(n))
-        .unwrap();
-    searched_nodes.insert(next_node);
-
-    let (order_unassigned, neighbors_unassigned) =
-        unassigned_order_and_neighbors(next_node, graph, &base_pattern);
-    let n_unassigned = neighbors_unassigned.len();
-    // if this node has no freedom to color edges,
-    // skip to the next one
-    if n_unassigned == 0 {
-        patterns.extend(search_vec_patterns_rec(graph, base_pattern, searched_nodes));
-        return patterns;
+/// All the square lattice patterns of the graph, skipping the bits of the
+/// rows and columns without used coupler.
+pub fn search_square_patterns(graph: &SearchGraph) -> impl Iterator<Item = SquarePattern> {
+    let (width, height) = (graph.config.width, graph.config.height);
+    let n_bits = (1 + width + height) as usize;
+    if n_bits >= 32 {
+        panic!(
+            "Number of patterns is too large! The sum of width and height should be less than 31."
+        );
     }
-    // recursively search for all the possible patterns
-    let allowed_orders = order_unassigned
+    let max_num: u32 = (1 << n_bits) - 1;
+    let live_bits: HashSet<_> = graph
+        .primal
+        .all_edges()
+        .filter(|e| *e.2)
+        .map(|(n1, n2, _)| square_bit_index(n1, n2, height))
+        .collect();
+    let dead_indices = (1..n_bits).filter(|i| !live_bits.contains(i)).collect_vec();
+    (0..=max_num)
+        .filter(move |n| dead_indices.iter().all(|&i| n & (1 << i) == 0))
+        .map(move |n| SquarePattern::from_bits(n, width, height))
+}
+
+/// Every pattern of the tiles with each of its orders of the links, see
+/// [`search_link_orders`].
+pub fn search_linked_patterns<'a, P: Pattern + Clone + 'a>(
+    graph: &'a SearchGraph,
+    patterns: impl IntoIterator<Item = P> + 'a,
+) -> impl Iterator<Item = LinkedPattern<P>> + 'a {
+    patterns.into_iter().flat_map(move |tiles| {
+        search_link_orders(graph, &tiles)
+            .into_iter()
+            .map(move |links| LinkedPattern {
+                tiles: tiles.clone(),
+                links,
+            })
+    })
+}
+
+/// All the orders of the links compatible with the pattern of the tiles, so
+/// that no qubit has two used couplers of the same order. A used link is
+/// left without order only when the other couplers of its qubits take all
+/// the orders, and so is an unused link.
+pub fn search_link_orders<P: Pattern>(graph: &SearchGraph, tiles: &P) -> Vec<Vec<Option<Order>>> {
+    let order_vec = tiles.order_vec(graph);
+    let taken = |q: Point| {
+        graph
+            .primal
+            .edges(q)
+            .filter(|&(n1, n2, _)| !graph.is_link(n1, n2))
+            .filter_map(|(n1, n2, _)| order_vec[graph.edge_index(n1, n2)])
+            .collect::<SmallVec<[Order; 4]>>()
+    };
+    let free = graph
+        .links
+        .iter()
+        .map(|&(n1, n2)| {
+            let used = *graph.primal.edge_weight(n1, n2).unwrap();
+            let (taken1, taken2) = (taken(n1), taken(n2));
+            let orders = Order::all_possibles()
+                .filter(|o| used && !taken1.contains(o) && !taken2.contains(o))
+                .collect::<SmallVec<[Order; 4]>>();
+            ((n1, n2), orders)
+        })
+        .collect_vec();
+    search_link_orders_rec(&free, Vec::new())
+}
+
+fn search_link_orders_rec(
+    free: &[(Edge, SmallVec<[Order; 4]>)],
+    base_orders: Vec<Option<Order>>,
+) -> Vec<Vec<Option<Order>>> {
+    let i = base_orders.len();
+    let Some(((n1, n2), orders)) = free.get(i) else {
+        return vec![base_orders];
+    };
+    // the orders of the previous links sharing a qubit with this one
+    let taken = free[..i]
+        .iter()
+        .zip(&base_orders)
+        .filter(|(((m1, m2), _), _)| [m1, m2].iter().any(|m| *m == n1 || *m == n2))
+        .filter_map(|(_, &order)| order)
+        .collect::<SmallVec<[Order; 4]>>();
+    let orders = orders
+        .iter()
+        .filter(|o| !taken.contains(o))
+        .map(|&o| Some(o))
+        .collect_vec();
+    let orders = if orders.is_empty() {
+        vec![None]
+    } else {
+        orders
+    };
+    orders
         .into_iter()
-        .permutations(n_unassigned)
-        .filter(|order| {
-            neighbors_unassigned
-                .iter()
-                .zip(order.iter())
-                .all(|(&neighbor, order)| {
-                    let (allowed_orders, _) =
-                        unassigned_order_and_neighbors(neighbor, graph, &base_pattern);
-                    allowed_orders.contains(order)
+        .flat_map(|order| {
+            let mut new_orders = base_orders.clone();
+            new_orders.push(order);
+            search_link_orders_rec(free, new_orders)
+        })
+        .collect()
+}
+
+/// All the colorings of the used couplers such that no qubit has two
+/// couplers of the same order, enumerated lazily in depth-first order so
+/// that taking the first patterns only searches them.
+pub fn search_vec_patterns(graph: &SearchGraph) -> impl Iterator<Item = VecPattern> + '_ {
+    let primal = &graph.primal;
+    let nodes = primal.nodes().collect_vec();
+    // the partial patterns left to extend, with the index of their next node
+    let mut stack = vec![(vec![None; primal.edge_count()], 0)];
+    from_fn(move || {
+        while let Some((base_pattern, i)) = stack.pop() {
+            let Some(&next_node) = nodes.get(i) else {
+                return Some(base_pattern);
+            };
+            let (order_unassigned, neighbors_unassigned) =
+                unassigned_order_and_neighbors(next_node, graph, &base_pattern);
+            let n_unassigned = neighbors_unassigned.len();
+            // if this node has no freedom to color edges,
+            // skip to the next one
+            if n_unassigned == 0 {
+                stack.push((base_pattern, i + 1));
+                continue;
+            }
+            let allowed_orders = order_unassigned
+                .into_iter()
+                .permutations(n_unassigned)
+                .filter(|order| {
+                    neighbors_unassigned
+                        .iter()
+                        .zip(order.iter())
+                        .all(|(&neighbor, order)| {
+                            let (allowed_orders, _) =
+                                unassigned_order_and_neighbors(neighbor, graph, &base_pattern);
+                            allowed_orders.contains(order)
+                        })
+                });
+            let new_patterns = allowed_orders
+                .map(|order| {
+                    let mut new_pattern = base_pattern.clone();
+                    for (&o, &neighbor) in order.iter().zip(neighbors_unassigned.iter()) {
+                        let index = graph.edge_index(next_node, neighbor);
+                        new_pattern[index] = Some(o);
+                    }
+                    (new_pattern, i + 1)
                 })
-        });
-    for order in allowed_orders {
-        let mut new_pattern = base_pattern.clone();
-        for (&o, &neighbor) in order.iter().zip(neighbors_unassigned.iter()) {
-            let index = get_edge_index(next_node, neighbor, (graph.config.width - 1) as usize);
-            new_pattern[index] = Some(o);
+                .collect_vec();
+            // popped in the order of the permutations
+            stack.extend(new_patterns.into_iter().rev());
         }
-        let searched_patterns = search_vec_patterns_rec(graph, new_pattern, searched_nodes.clone());
-        patterns.extend(searched_patterns);
-    }
-    patterns
+        None
+    })
 }
 
 fn unassigned_order_and_neighbors(
@@ -131,6 +254,7 @@ fn unassigned_order_and_neighbors(
 mod tests {
     use super::*;
     use crate::config::{TopologyConfig, TopologyConfigBuilder};
+    use crate::graph::Lattice;
 
     macro_rules! test_n_bit_pattern {
         ($unused:expr, $nbits:expr) => {
@@ -149,6 +273,96 @@ mod tests {
         test_n_bit_pattern!([21], 21);
     }
 
+    #[test]
+    fn test_hex_pattern_number() {
+        let config = TopologyConfigBuilder::default()
+            .width(7)
+            .height(3)
+            .lattice(Lattice::HeavyHex)
+            .build()
+            .unwrap();
+        let graph = SearchGraph::from_config(config).unwrap();
+        assert_eq!(search_hex_patterns(&graph).count(), 1 << 6);
+
+        // the chain of the last row is dead
+        let config = TopologyConfigBuilder::default()
+            .width(7)
+            .height(3)
+            .lattice(Lattice::HeavyHex)
+            .unused_qubits((18..25).collect())
+            .build()
+            .unwrap();
+        let graph = SearchGraph::from_config(config).unwrap();
+        assert_eq!(search_hex_patterns(&graph).count(), 1 << 5);
+    }
+
+    #[test]
+    fn test_square_pattern_number() {
+        let config = TopologyConfigBuilder::default()
+            .width(5)
+            .height(4)
+            .lattice(Lattice::Square)
+            .build()
+            .unwrap();
+        let graph = SearchGraph::from_config(config).unwrap();
+        assert_eq!(search_square_patterns(&graph).count(), 1 << 10);
+
+        // the last column has no vertical coupler left
+        let config = TopologyConfigBuilder::default()
+            .width(5)
+            .height(4)
+            .lattice(Lattice::Square)
+            .unused_couplers(vec![(4, 9), (9, 14), (14, 19)])
+            .build()
+            .unwrap();
+        let graph = SearchGraph::from_config(config).unwrap();
+        assert_eq!(search_square_patterns(&graph).count(), 1 << 9);
+    }
+
+    #[test]
+    fn test_linked_patterns() {
+        let config = TopologyConfigBuilder::default()
+            .width(8)
+            .height(4)
+            .tiles(vec!["4x4@0,0".parse().unwrap(), "4x4@4,0".parse().unwrap()])
+            .links(vec![(1, 14), (5, 10), (9, 2)])
+            .build()
+            .unwrap();
+        let graph = SearchGraph::from_config(config).unwrap();
+        let tiles = search_bit_patterns(&graph).take(16).collect_vec();
+        let patterns = search_linked_patterns(&graph, tiles.clone()).collect_vec();
+        assert!(patterns.len() >= tiles.len());
+        for pattern in &patterns {
+            let order_vec = pattern.order_vec(&graph);
+            // the links keep the orders of the tiles
+            let tiles_vec = pattern.tiles.order_vec(&graph);
+            for (i, &(n1, n2)) in graph.edges.iter().enumerate() {
+                if !graph.is_link(n1, n2) {
+                    assert_eq!(order_vec[i], tiles_vec[i]);
+                }
+            }
+            // no qubit has two couplers of the same order
+            for q in graph.primal.nodes() {
+                let orders = graph
+                    .primal
+                    .edges(q)
+                    .filter_map(|(n1, n2, _)| order_vec[graph.edge_index(n1, n2)])
+                    .collect_vec();
+                assert_eq!(orders.iter().unique().count(), orders.len(), "{:?}", q);
+            }
+            assert!(pattern.links.iter().any(|o| o.is_some()));
+            assert!(pattern.describe(&graph).to_string().contains('+'));
+        }
+        assert_eq!(patterns.iter().unique().count(), patterns.len());
+
+        // the dead link takes no order
+        let mut config = graph.config.clone();
+        config.unused_couplers.push((14, 1));
+        let graph = SearchGraph::from_config(config).unwrap();
+        let link_orders = search_link_orders(&graph, &tiles[0]);
+        assert!(link_orders.iter().all(|orders| orders[0].is_none()));
+    }
+
     #[test]
     fn test_vec_pattern() {
         let config = TopologyConfigBuilder::default()
@@ -157,8 +371,14 @@ mod tests {
             .build()
             .unwrap();
         let graph = SearchGraph::from_config(config).unwrap();
-        let patterns = search_vec_patterns(&graph);
+        let patterns = search_vec_patterns(&graph).collect_vec();
         println!("Found {} patterns", patterns.len());
-        assert_eq!(patterns.len(), 168)
+        assert_eq!(patterns.len(), 168);
+        assert_eq!(patterns.iter().unique().count(), 168);
+        // the first patterns are found without enumerating the others
+        assert_eq!(
+            search_vec_patterns(&graph).take(10).collect_vec(),
+            patterns[..10]
+        );
     }
 }
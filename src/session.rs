@@ -0,0 +1,200 @@
+use crate::cancel::CancelToken;
+use crate::config::AlgorithmConfig;
+use crate::cost::{max_min_cost_wrapped, min_cost_record, CostModelKind, Record, SearchResult};
+use crate::cutline::{search_cutlines, Cutline, CutlineError, CutlineWrapped};
+use crate::graph::SearchGraph;
+use crate::pattern::{Order, Pattern};
+use crate::progress::ProgressObserver;
+use itertools::Itertools;
+
+/// Owns a graph together with its enumerated and wrapped cutlines, so that
+/// repeated queries on the same topology skip the cutline enumeration.
+///
+/// Only the settings which do not affect the cutlines, i.e. the ordering,
+/// the cost model and the time budget, can be changed after creation.
+#[derive(Debug, Clone)]
+pub struct SearchSession {
+    graph: SearchGraph,
+    algorithm: AlgorithmConfig,
+    cutlines: Vec<Cutline>,
+    cutlines_wrapped: Vec<CutlineWrapped>,
+}
+
+impl SearchSession {
+    /// Enumerate the cutlines of the graph, see [`search_cutlines`]. Fails
+    /// with [`CutlineError::NoCutlines`] if there is none.
+    pub fn new(graph: SearchGraph, algorithm: AlgorithmConfig) -> Result<Self, CutlineError> {
+        let cutlines = search_cutlines(&graph, &algorithm)?;
+        if cutlines.is_empty() {
+            return Err(CutlineError::NoCutlines);
+        }
+        let cutlines_wrapped = cutlines
+            .iter()
+            .cloned()
+            .map(|c| c.into_wrapped(&graph))
+            .collect_vec();
+        Ok(Self {
+            graph,
+            algorithm,
+            cutlines,
+            cutlines_wrapped,
+        })
+    }
+
+    pub fn graph(&self) -> &SearchGraph {
+        &self.graph
+    }
+
+    pub fn algorithm(&self) -> &AlgorithmConfig {
+        &self.algorithm
+    }
+
+    pub fn cutlines(&self) -> &[Cutline] {
+        &self.cutlines
+    }
+
+    pub(crate) fn cutlines_wrapped(&self) -> &[CutlineWrapped] {
+        &self.cutlines_wrapped
+    }
+
+    pub fn set_ordering(&mut self, ordering: Vec<Order>) {
+        self.algorithm.ordering = ordering;
+    }
+
+    pub fn set_cost_model(&mut self, cost_model: CostModelKind) {
+        self.algorithm.cost_model = cost_model;
+    }
+
+    pub fn set_time_budget(&mut self, time_budget: Option<u64>) {
+        self.algorithm.time_budget = time_budget;
+    }
+
+    /// Evaluate the patterns with the max-min search, see
+    /// [`max_min_cost`](crate::cost::max_min_cost).
+    pub fn evaluate<P: Pattern + Sync>(
+        &self,
+        patterns: Vec<P>,
+        observer: &dyn ProgressObserver,
+        cancel: &CancelToken,
+    ) -> SearchResult {
+        self.evaluate_with(patterns, &self.algorithm, observer, cancel)
+    }
+
+    /// Evaluate the patterns under another ordering, without changing the
+    /// ordering of the session.
+    pub fn evaluate_with_ordering<P: Pattern + Sync>(
+        &self,
+        patterns: Vec<P>,
+        ordering: &[Order],
+        observer: &dyn ProgressObserver,
+        cancel: &CancelToken,
+    ) -> SearchResult {
+        let algorithm = AlgorithmConfig {
+            ordering: ordering.to_vec(),
+            ..self.algorithm.clone()
+        };
+        self.evaluate_with(patterns, &algorithm, observer, cancel)
+    }
+
+    /// Find the cutline with the minimum cost for a single pattern.
+    pub fn min_cutline<P: Pattern>(&self, pattern: &P) -> Record {
+        let model = self.algorithm.cost_model.build(&self.graph);
+        // a session has at least one cutline
+        min_cost_record(
+            &self.graph,
+            pattern,
+            &self.cutlines_wrapped,
+            &self.algorithm,
+            model.as_ref(),
+        )
+        .unwrap()
+    }
+
+    fn evaluate_with<P: Pattern + Sync>(
+        &self,
+        patterns: Vec<P>,
+        algorithm: &AlgorithmConfig,
+        observer: &dyn ProgressObserver,
+        cancel: &CancelToken,
+    ) -> SearchResult {
+        let model = algorithm.cost_model.build(&self.graph);
+        max_min_cost_wrapped(
+            &self.graph,
+            &patterns,
+            &self.cutlines_wrapped,
+            algorithm,
+            model.as_ref(),
+            observer,
+            cancel,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cost::max_min_cost;
+    use crate::pattern::pattern_from_repr;
+    use crate::progress::SilentProgress;
+    use crate::search_pattern::search_bit_patterns;
+    use crate::test_util::grid_search;
+
+    #[test]
+    fn test_session_queries() {
+        let (graph, algo, cutlines) = grid_search(vec![]);
+        let mut session = SearchSession::new(graph.clone(), algo.clone()).unwrap();
+        assert_eq!(session.cutlines(), &cutlines[..]);
+
+        let patterns = search_bit_patterns(&graph).collect_vec();
+        let cancel = CancelToken::new();
+        let expected = max_min_cost(
+            &graph,
+            patterns.clone(),
+            cutlines.clone(),
+            &algo,
+            &SilentProgress,
+            &cancel,
+        );
+        let result = session.evaluate(patterns.clone(), &SilentProgress, &cancel);
+        assert_eq!(result.records.len(), expected.records.len());
+        assert_eq!(result.records[0].score, expected.records[0].score);
+
+        let pattern = pattern_from_repr("0_0110_1_1001").unwrap();
+        let record = session.min_cutline(&pattern);
+        let single = session.evaluate(vec![pattern.clone()], &SilentProgress, &cancel);
+        assert_eq!(record.cost, single.records[0].cost);
+        assert_eq!(record.cutline, single.records[0].cutline);
+
+        let ordering = "ABCDCDAB"
+            .chars()
+            .map(|c| Order::try_from(c).unwrap())
+            .collect_vec();
+        let result =
+            session.evaluate_with_ordering(patterns.clone(), &ordering, &SilentProgress, &cancel);
+        session.set_ordering(ordering);
+        let expected = max_min_cost(
+            &graph,
+            patterns,
+            cutlines,
+            session.algorithm(),
+            &SilentProgress,
+            &cancel,
+        );
+        assert_eq!(result.records.len(), expected.records.len());
+        assert_eq!(result.records[0].score, expected.records[0].score);
+    }
+
+    #[test]
+    fn test_session_without_cutlines() {
+        let (graph, algo, _) = grid_search(vec![]);
+        let algo = AlgorithmConfig {
+            max_depth: 1,
+            ..algo
+        };
+        assert!(search_cutlines(&graph, &algo).unwrap().is_empty());
+        assert!(matches!(
+            SearchSession::new(graph, algo),
+            Err(CutlineError::NoCutlines)
+        ));
+    }
+}
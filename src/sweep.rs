@@ -0,0 +1,321 @@
+//! Sensitivity of the optimal cost to a single dead qubit or coupler.
+//!
+//! [`defect_sweep`] disables every live qubit and every live coupler of the
+//! topology of a [`SearchSession`] one at a time, and reruns the cutline
+//! search and the max-min cost on each variant. The variants are ranked by
+//! their optimal score, the defects which lower it the most come first.
+//!
+//! The variants are edited from the graph of the session with
+//! [`SearchGraph::kill`]. Unless the defect touches the boundary or the
+//! graph has links, the cutline search is not run again from scratch: the
+//! cutlines of the session graph which stay valid come from
+//! [`GraphUpdate::valid_cutlines`](crate::edit::GraphUpdate::valid_cutlines),
+//! and the defect only adds the cutlines crossing one of its couplers, whose
+//! depth dropped. The session cutlines are taken without unbalance limit
+//! here, as the defect changes their unbalance.
+//!
+//! The cost of a cutline only depends on its used split edges, its fusion
+//! candidates, its unbalance and the number of used qubits. A cutline of the
+//! variant identical to a cutline of the session on all of them has the same
+//! cost for every pattern, so a pattern whose optimal cutline survives the
+//! defect keeps its optimal cost unless one of the new or changed cutlines
+//! is cheaper. Only those cutlines are evaluated for such patterns.
+use crate::cancel::CancelToken;
+use crate::config::AlgorithmConfig;
+use crate::cost::{calculate_min_cost, min_costs, OrderInfo};
+use crate::cutline::{search_cutlines, search_cutlines_crossing, CutlineWrapped};
+use crate::defect::Defect;
+use crate::graph::SearchGraph;
+use crate::pattern::{Pattern, PatternDescription};
+use crate::session::SearchSession;
+use anyhow::Result;
+use fixedbitset::FixedBitSet;
+use itertools::Itertools;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+
+/// The optimal cost of the topology with one more dead qubit or coupler.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SweepEntry {
+    /// The disabled qubit or coupler, by its coordinates
+    pub defect: Defect,
+    /// The optimal score of the variant, `None` if the variant is rejected
+    pub score: Option<f64>,
+    /// The optimal score of the variant over the optimal score of the
+    /// session
+    pub ratio: Option<f64>,
+    /// The patterns owning the optimal cutlines of the variant
+    pub best_patterns: Vec<PatternDescription>,
+    /// Whether the best patterns differ from those of the session
+    pub pattern_changed: bool,
+    pub n_cutlines: usize,
+    /// Number of cutlines identical to a cutline of the session
+    pub n_reused: usize,
+    /// Why the variant is rejected, e.g. the defect disconnects the chip
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DefectSweep {
+    pub base_score: f64,
+    pub base_patterns: Vec<PatternDescription>,
+    /// The variants ranked by their score in ascending order, the rejected
+    /// variants first
+    pub entries: Vec<SweepEntry>,
+    /// Whether the sweep was cancelled before all the defects were tried
+    pub partial: bool,
+}
+
+impl DefectSweep {
+    pub fn save_to_json(&self, path: &Path) -> Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+/// Disable each live qubit and coupler of the session topology in turn and
+/// find the optimal score of the patterns on each variant.
+///
+/// A session has at least one cutline, so the base score is always defined.
+pub fn defect_sweep<P: Pattern + Sync>(
+    session: &SearchSession,
+    patterns: &[P],
+    cancel: &CancelToken,
+) -> DefectSweep {
+    let graph = session.graph();
+    let algorithm = session.algorithm();
+    let base_wrapped = session.cutlines_wrapped();
+    let model = algorithm.cost_model.build(graph);
+    // a session has at least one cutline
+    let base_costs = min_costs(graph, patterns, base_wrapped, algorithm, model.as_ref()).unwrap();
+    let scores = base_costs.iter().map(|&(_, _, score)| score).collect_vec();
+    let (base_score, base_patterns) = best_patterns(graph, patterns, &scores);
+    let base_index: HashMap<&CutlineWrapped, usize> = base_wrapped
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (c, i))
+        .collect();
+    let unlimited = AlgorithmConfig {
+        max_unbalance: usize::MAX,
+        ..algorithm.clone()
+    };
+    // the search on the session graph cannot fail again
+    let base_cutlines = if graph.links.is_empty() {
+        search_cutlines(graph, &unlimited).unwrap()
+    } else {
+        Vec::new()
+    };
+
+    let qubits = graph
+        .primal
+        .nodes()
+        .filter(|q| !graph.unused_qubits.contains(q))
+        .map(Defect::Qubit);
+    let couplers = graph
+        .primal
+        .all_edges()
+        .filter(|e| *e.2)
+        .map(|(n1, n2, _)| Defect::Coupler(n1.min(n2), n1.max(n2)));
+    let mut entries = Vec::new();
+    let mut partial = false;
+    for defect in qubits.chain(couplers) {
+        if cancel.is_cancelled() {
+            partial = true;
+            break;
+        }
+        let mut variant = graph.clone();
+        let update = match variant.kill(defect.clone()) {
+            Ok(update) => update,
+            Err(e) => {
+                entries.push(rejected(defect, e.to_string()));
+                continue;
+            }
+        };
+        let cutlines = if graph.links.is_empty() && update.dual_nodes.is_empty() {
+            search_cutlines_crossing(&variant, algorithm, &update.couplers).map(|crossing| {
+                update
+                    .valid_cutlines(&variant, &base_cutlines, algorithm)
+                    .into_iter()
+                    .map(|(_, cutline)| cutline)
+                    .chain(crossing)
+                    .collect_vec()
+            })
+        } else {
+            search_cutlines(&variant, algorithm)
+        };
+        let cutlines = match cutlines {
+            Ok(cutlines) => cutlines
+                .into_iter()
+                .map(|c| c.into_wrapped(&variant))
+                // a cutline may be found in both directions
+                .unique_by(|c| c.split.iter().copied().sorted().collect_vec())
+                .collect_vec(),
+            Err(e) => {
+                entries.push(rejected(defect, e.to_string()));
+                continue;
+            }
+        };
+        if cutlines.is_empty() {
+            entries.push(rejected(defect, "no valid cutline".to_string()));
+            continue;
+        }
+        // the scores depend on the number of used qubits
+        let same_qubits = variant.unused_qubits.len() == graph.unused_qubits.len();
+        let mut survived = FixedBitSet::with_capacity(base_wrapped.len());
+        let mut changed = Vec::new();
+        for cutline in &cutlines {
+            match base_index.get(cutline) {
+                Some(&i) if same_qubits => survived.insert(i),
+                _ => changed.push(cutline.clone()),
+            }
+        }
+        let model = algorithm.cost_model.build(&variant);
+        let order_info = OrderInfo::new(&algorithm.ordering);
+        let scores = patterns
+            .par_iter()
+            .zip(&base_costs)
+            .map(|(pattern, &(i, _, base_score))| {
+                let order_vec = pattern.order_vec(&variant);
+                let min_score = |cutlines: &[CutlineWrapped]| {
+                    calculate_min_cost(&variant, &order_vec, cutlines, &order_info, model.as_ref())
+                        .map(|min_cost| min_cost.2)
+                };
+                if !survived[i] {
+                    // the variant has at least one cutline
+                    return min_score(&cutlines).unwrap();
+                }
+                min_score(&changed).map_or(base_score, |score| score.min(base_score))
+            })
+            .collect::<Vec<_>>();
+        let (score, best_patterns) = best_patterns(&variant, patterns, &scores);
+        entries.push(SweepEntry {
+            defect,
+            score: Some(score),
+            ratio: Some(score / base_score),
+            pattern_changed: best_patterns != base_patterns,
+            best_patterns,
+            n_cutlines: cutlines.len(),
+            n_reused: cutlines.len() - changed.len(),
+            error: None,
+        });
+    }
+    entries.sort_by(|e1, e2| match (e1.score, e2.score) {
+        (Some(s1), Some(s2)) => s1.partial_cmp(&s2).unwrap(),
+        (s1, s2) => s1.is_some().cmp(&s2.is_some()),
+    });
+    DefectSweep {
+        base_score,
+        base_patterns,
+        entries,
+        partial,
+    }
+}
+
+/// The maximum score and the sorted descriptions of the patterns reaching
+/// it.
+fn best_patterns<P: Pattern>(
+    graph: &SearchGraph,
+    patterns: &[P],
+    scores: &[f64],
+) -> (f64, Vec<PatternDescription>) {
+    let best = scores.iter().copied().fold(0f64, f64::max);
+    let descriptions = patterns
+        .iter()
+        .zip(scores)
+        .filter(|&(_, &score)| score == best)
+        .map(|(pattern, _)| pattern.describe(graph))
+        .sorted()
+        .collect();
+    (best, descriptions)
+}
+
+fn rejected(defect: Defect, error: String) -> SweepEntry {
+    SweepEntry {
+        defect,
+        score: None,
+        ratio: None,
+        best_patterns: Vec::new(),
+        pattern_changed: true,
+        n_cutlines: 0,
+        n_reused: 0,
+        error: Some(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AlgorithmConfigBuilder, TopologyConfigBuilder};
+    use crate::cost::max_min_cost;
+    use crate::progress::SilentProgress;
+    use crate::search_pattern::search_bit_patterns;
+
+    #[test]
+    fn test_defect_sweep() {
+        let config = TopologyConfigBuilder::default()
+            .width(5)
+            .height(5)
+            .unused_qubits(vec![0])
+            .build()
+            .unwrap();
+        let algo = AlgorithmConfigBuilder::default()
+            .max_depth(5)
+            .build()
+            .unwrap();
+        let graph = SearchGraph::from_config(config.clone()).unwrap();
+        let session = SearchSession::new(graph.clone(), algo.clone()).unwrap();
+        let patterns = search_bit_patterns(&graph).collect_vec();
+        let cancel = CancelToken::new();
+        let sweep = defect_sweep(&session, &patterns, &cancel);
+        assert!(!sweep.partial);
+        let base = session.evaluate(patterns.clone(), &SilentProgress, &cancel);
+        assert_eq!(sweep.base_score, base.records[0].score);
+
+        let n_qubits = graph.primal.node_count() - 1;
+        let n_couplers = graph.primal.all_edges().filter(|e| *e.2).count();
+        assert_eq!(sweep.entries.len(), n_qubits + n_couplers);
+        assert!(sweep
+            .entries
+            .iter()
+            .tuple_windows()
+            .all(|(e1, e2)| e1.score.is_none() || e1.score <= e2.score));
+        assert!(sweep.entries.iter().any(|e| e.n_reused > 0));
+
+        // the reused costs match a search from scratch
+        for entry in &sweep.entries {
+            let mut variant_config = config.clone();
+            variant_config.defects.push(entry.defect.clone());
+            let Ok(variant) = SearchGraph::from_config(variant_config) else {
+                assert!(entry.error.is_some());
+                continue;
+            };
+            let cutlines = search_cutlines(&variant, &algo).unwrap();
+            assert_eq!(entry.n_cutlines, cutlines.len(), "{}", entry.defect);
+            let result = max_min_cost(
+                &variant,
+                patterns.clone(),
+                cutlines,
+                &algo,
+                &SilentProgress,
+                &cancel,
+            );
+            assert_eq!(
+                entry.score,
+                Some(result.records[0].score),
+                "{}",
+                entry.defect
+            );
+            let expected = result
+                .records
+                .iter()
+                .map(|r| r.pattern.clone())
+                .dedup()
+                .collect_vec();
+            assert_eq!(entry.best_patterns, expected, "{}", entry.defect);
+        }
+    }
+}
@@ -0,0 +1,22 @@
+//! Fixtures shared by the tests of several modules.
+use crate::config::{AlgorithmConfig, AlgorithmConfigBuilder, TopologyConfigBuilder};
+use crate::cutline::{search_cutlines, Cutline};
+use crate::graph::SearchGraph;
+
+/// The 6x5 grid with the given unused qubits, searched up to depth 6, with
+/// its cutlines.
+pub(crate) fn grid_search(unused_qubits: Vec<u32>) -> (SearchGraph, AlgorithmConfig, Vec<Cutline>) {
+    let config = TopologyConfigBuilder::default()
+        .width(6)
+        .height(5)
+        .unused_qubits(unused_qubits)
+        .build()
+        .unwrap();
+    let graph = SearchGraph::from_config(config).unwrap();
+    let algo = AlgorithmConfigBuilder::default()
+        .max_depth(6)
+        .build()
+        .unwrap();
+    let cutlines = search_cutlines(&graph, &algo).unwrap();
+    (graph, algo, cutlines)
+}